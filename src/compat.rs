@@ -0,0 +1,56 @@
+//! Compatibility shim for code written against WURFL capability names.
+
+use super::device_detection::{PropertyName, ResultData, nearest_known_property};
+use super::utils::FiftyOneDegreesError::UnknownCapability;
+use super::utils::FiftyOneDegreesResult;
+
+/// WURFL capabilities with the property holding the same information.
+const CAPABILITIES: &[(&str, PropertyName)] = &[
+    ("brand_name", PropertyName::HardwareVendor),
+    ("model_name", PropertyName::HardwareModel),
+    ("marketing_name", PropertyName::HardwareName),
+    ("device_os", PropertyName::PlatformName),
+    ("device_os_version", PropertyName::PlatformVersion),
+    ("mobile_browser", PropertyName::BrowserName),
+    ("mobile_browser_version", PropertyName::BrowserVersion),
+    ("advertised_browser", PropertyName::BrowserName),
+    ("advertised_browser_version", PropertyName::BrowserVersion),
+    ("advertised_device_os", PropertyName::PlatformName),
+    (
+        "advertised_device_os_version",
+        PropertyName::PlatformVersion,
+    ),
+    ("is_wireless_device", PropertyName::IsMobile),
+    ("is_tablet", PropertyName::IsTablet),
+    ("is_smarttv", PropertyName::IsTv),
+    ("is_robot", PropertyName::IsCrawler),
+    ("resolution_width", PropertyName::ScreenPixelsWidth),
+    ("resolution_height", PropertyName::ScreenPixelsHeight),
+    (
+        "physical_screen_diagonal",
+        PropertyName::ScreenInchesDiagonal,
+    ),
+    ("release_date", PropertyName::ReleaseYear),
+];
+
+/// Returns the property matching the WURFL capability.
+///
+/// Fails with `UnknownCapability`, naming the closest property, for capabilities without one.
+pub fn property_for_capability(wurfl_name: &str) -> FiftyOneDegreesResult<PropertyName> {
+    CAPABILITIES
+        .iter()
+        .find(|(name, _)| *name == wurfl_name)
+        .map(|(_, property)| *property)
+        .ok_or_else(|| UnknownCapability {
+            name: wurfl_name.to_string(),
+            nearest: nearest_known_property(wurfl_name),
+        })
+}
+
+impl ResultData {
+    /// Reads the value of the property matching the WURFL capability, see
+    /// [`property_for_capability`].
+    pub fn capability(&self, wurfl_name: &str) -> FiftyOneDegreesResult<Option<String>> {
+        self.get_value_as_string(property_for_capability(wurfl_name)?)
+    }
+}
@@ -1,19 +1,38 @@
 use super::bindings;
 use super::utils::{
-    build_cstring, status_to_error_message, verify_data_file_path, verify_exception, CStringKind,
-    FiftyOneDegreesResult, Operation,
+    build_cstring, build_cstring_from_bytes, new_exception, read_fiftyone_string,
+    status_to_error_message, verify_data_file_path, verify_exception, CStringKind,
+    FiftyOneDegreesResult, Operation, StatusCode,
 };
 use super::utils::FiftyOneDegreesError::{
-    AssertionError, IOError, InternalApiError, UnsafeOperationError,
+    AssertionError, CStringCreationError, IOError, InternalApiError, ManagerPoisoned,
+    PropertyNotExposed, RequiredPropertyMissing, TempFileError, TierMismatch, UnsafeOperationError,
+    ValueParse,
 };
+use super::utils::FiftyOneDegreesError;
+#[cfg(feature = "structured-hints")]
+use base64::Engine;
 use itertools::Itertools;
 use std::borrow::Cow;
-use std::ffi::{CStr, CString};
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ffi::{c_char, CStr, CString};
+#[cfg(unix)]
+use std::fs::File;
+use std::io::BufRead;
+#[cfg(unix)]
+use std::io::Read;
+#[cfg(unix)]
+use std::mem::ManuallyDrop;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
 use std::ptr::null_mut;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, SystemTime};
 use strum_macros::{AsRefStr, Display};
 
-#[derive(Debug, Clone, Copy, PartialEq, Display, AsRefStr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Display, AsRefStr)]
 pub enum PropertyName {
     // Device info
     DeviceId,
@@ -116,6 +135,181 @@ impl PropertyName {
             _ => self.as_ref(),
         }
     }
+
+    /// Returns the name as passed to the C library, validated to be non-empty and NUL-free.
+    ///
+    /// Unlike [`PropertyName::to_str`] this is safe to use on `Custom` names from untrusted input.
+    pub fn to_c_key(&self) -> FiftyOneDegreesResult<Cow<'_, str>> {
+        let key = self.to_str();
+        if key.is_empty() {
+            return Err(AssertionError(
+                Operation::ReadProperty,
+                "Property name must not be empty",
+            ));
+        }
+        if key.contains('\0') {
+            return Err(CStringCreationError(CStringKind::PropertyName));
+        }
+        Ok(Cow::Borrowed(key))
+    }
+
+    /// Returns the name as a C string, shared for known properties and allocated for `Custom`.
+    fn to_cstring(self) -> FiftyOneDegreesResult<Cow<'static, CStr>> {
+        match self {
+            PropertyName::Custom(s) => Ok(Cow::Owned(build_cstring(CStringKind::PropertyName, s)?)),
+            _ => KNOWN_PROPERTY_CSTRINGS
+                .get(&self)
+                .map(|name| Cow::Borrowed(name.as_c_str()))
+                .ok_or(CStringCreationError(CStringKind::PropertyName)),
+        }
+    }
+
+    /// Resolves a known property name, panicking with the nearest known name otherwise.
+    ///
+    /// Used by [`property!`](crate::property) in a const context, so the panic is reported
+    /// as a compile error.
+    #[doc(hidden)]
+    pub const fn from_known_name(name: &str) -> PropertyName {
+        let mut nearest = 0;
+        let mut nearest_distance = usize::MAX;
+        let mut i = 0;
+        while i < KNOWN_PROPERTIES.len() {
+            let known = KNOWN_PROPERTIES[i].name.as_bytes();
+            if const_bytes_eq(known, name.as_bytes()) {
+                return KNOWN_PROPERTIES[i].property;
+            }
+            let distance = const_edit_distance(known, name.as_bytes());
+            if distance < nearest_distance {
+                nearest = i;
+                nearest_distance = distance;
+            }
+            i += 1;
+        }
+        panic!("{}", KNOWN_PROPERTIES[nearest].unknown_message)
+    }
+}
+
+/// Builds a [`PropertyName`] from a string literal, checked at compile time.
+///
+/// Names outside of the known [`PropertyName`] variants fail to compile with a
+/// suggestion of the nearest known name; use [`PropertyName::Custom`] for those.
+///
+/// ```
+/// use fiftyonedegrees::device_detection::PropertyName;
+///
+/// assert_eq!(fiftyonedegrees::property!("BrowserName"), PropertyName::BrowserName);
+/// ```
+///
+/// ```compile_fail
+/// let _ = fiftyonedegrees::property!("BrowserNmae");
+/// ```
+#[macro_export]
+macro_rules! property {
+    ($name:literal) => {{
+        const PROPERTY: $crate::device_detection::PropertyName =
+            $crate::device_detection::PropertyName::from_known_name($name);
+        PROPERTY
+    }};
+}
+
+struct KnownProperty {
+    name: &'static str,
+    property: PropertyName,
+    unknown_message: &'static str,
+}
+
+macro_rules! known_properties {
+    ($($variant:ident),* $(,)?) => {
+        &[$(KnownProperty {
+            name: stringify!($variant),
+            property: PropertyName::$variant,
+            unknown_message: concat!("unknown property name, did you mean `", stringify!($variant), "`?"),
+        }),*]
+    };
+}
+
+/// Names of the known properties, created once to avoid an allocation per property read.
+static KNOWN_PROPERTY_CSTRINGS: LazyLock<HashMap<PropertyName, CString>> = LazyLock::new(|| {
+    KNOWN_PROPERTIES
+        .iter()
+        .filter_map(|known| Some((known.property, CString::new(known.name).ok()?)))
+        .collect()
+});
+
+const KNOWN_PROPERTIES: &[KnownProperty] = known_properties![
+    DeviceId, DeviceType, CrawlerName,
+    HasTouchScreen, IsScreenFoldable, IsSmallScreen, IsEmailBrowser, IsEmulatingDesktop,
+    IsEmulatingDevice, IsWebApp, IsConsole, IsEReader, IsMediaHub, IsMobile, IsSmartWatch,
+    IsTablet, IsTv, IsCrawler, IsArtificialIntelligence,
+    NativeBrand, NativeDevice, NativeModel, NativeName, NativePlatform,
+    BrowserFamily, BrowserName, BrowserVendor, BrowserVersion, BrowserReleaseYear,
+    BrowserSourceProject, BrowserSourceProjectVersion, BrowserRank,
+    Canvas, CookiesCapable, CssCanvas, DeviceOrientation, Fetch, Fullscreen, GeoLocation,
+    IndexedDB, InVRMode, Javascript, Viewport,
+    PlatformName, PlatformVendor, PlatformVersion, PlatformReleaseYear, PlatformRank,
+    HardwareName, HardwareVendor, HardwareFamily, HardwareModel, HardwareModelVariants,
+    HardwareCarrier, HardwareRank, OEM, ReleaseYear,
+    BitsPerPixel, PixelRatio, ScreenInchesDiagonal, ScreenPixelsHeight,
+    ScreenPixelsPhysicalHeight, ScreenPixelsPhysicalWidth, ScreenPixelsWidth, ScreenType,
+    RegisteredCountry, RegisteredName, RegisteredOwner,
+    Profiles, Popularity, PriceBand, Difference, Drift, UserAgents,
+];
+
+/// Returns the known property with the name nearest to `name`, ignoring case and underscores.
+pub(crate) fn nearest_known_property(name: &str) -> PropertyName {
+    let normalize = |name: &str| name.replace('_', "").to_ascii_lowercase();
+    let name = normalize(name);
+    KNOWN_PROPERTIES
+        .iter()
+        .min_by_key(|known| const_edit_distance(normalize(known.name).as_bytes(), name.as_bytes()))
+        .map_or(PropertyName::DeviceId, |known| known.property)
+}
+
+const fn const_bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Levenshtein distance usable in const context, `b` is truncated to 63 bytes.
+const fn const_edit_distance(a: &[u8], b: &[u8]) -> usize {
+    const MAX_LEN: usize = 63;
+    let b_len = if b.len() > MAX_LEN { MAX_LEN } else { b.len() };
+    let mut row = [0_usize; MAX_LEN + 1];
+    let mut j = 0;
+    while j <= b_len {
+        row[j] = j;
+        j += 1;
+    }
+    let mut i = 1;
+    while i <= a.len() {
+        let mut diagonal = row[0];
+        row[0] = i;
+        let mut j = 1;
+        while j <= b_len {
+            let above = row[j];
+            let mut best = diagonal + if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            if above + 1 < best {
+                best = above + 1;
+            }
+            if row[j - 1] + 1 < best {
+                best = row[j - 1] + 1;
+            }
+            row[j] = best;
+            diagonal = above;
+            j += 1;
+        }
+        i += 1;
+    }
+    row[b_len]
 }
 
 #[derive(Debug, Clone, PartialEq, Display, AsRefStr)]
@@ -126,6 +320,10 @@ pub enum EvidenceName {
     SecChUa,
     #[strum(serialize = "sec-ch-platform")]
     SecChPlatform,
+    #[strum(serialize = "sec-ch-ua-wow64")]
+    SecChUaWow64,
+    #[strum(serialize = "sec-ch-ua-form-factors")]
+    SecChUaFormFactors,
 
     // For unspecified fields
     Custom(&'static str),
@@ -142,20 +340,356 @@ impl EvidenceName {
             _ => self.as_ref(),
         }
     }
+
+    /// Returns the header name passed to the C library, see [`header_key`].
+    fn header_key(&self) -> Cow<'_, str> {
+        header_key(self.as_str())
+    }
+}
+
+/// Lowercases a header name, as the names of the `EvidenceName` variants are.
+fn header_key(name: &str) -> Cow<'_, str> {
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
 }
 
 type ResourceManager = Box<bindings::fiftyoneDegreesResourceManager>;
 type Properties = bindings::fiftyoneDegreesPropertiesRequired;
 type ConfigHash = bindings::fiftyoneDegreesConfigHash;
 
+/// Temp directory of a data set, referred to by its config for the whole data set lifetime.
+struct TempDir {
+    _dir_cstring: CString,
+    dirs: Box<[*const c_char]>,
+}
+
+// The pointers only refer to the C string owned by the same value, which is never modified.
+unsafe impl Send for TempDir {}
+unsafe impl Sync for TempDir {}
+
+impl TempDir {
+    fn new(dir: &Path) -> FiftyOneDegreesResult<Self> {
+        let dir_cstring = dir
+            .to_str()
+            .ok_or(IOError("Failed to convert temp dir path to string", None))
+            .and_then(|s| build_cstring(CStringKind::FilePath, s))?;
+        let dirs = Box::new([dir_cstring.as_ptr()]);
+        Ok(Self {
+            _dir_cstring: dir_cstring,
+            dirs,
+        })
+    }
+}
+
 pub struct ManagerConfig {
-    pub data_file_path: &'static Path,
-    pub property_names: Option<&'static [PropertyName]>,
+    pub data_file_path: PathBuf,
+    pub property_names: Option<Vec<PropertyName>>,
+    /// Trade-off between memory use and detection speed, see [`PerformanceProfile`].
+    /// `HighPerformance` if `None`, or `BalancedTemp` with a `temp_dir`.
+    pub performance_profile: Option<PerformanceProfile>,
+    /// Expected number of concurrent operations, applied to every collection of the data set.
+    /// Each unit allocates a handle per collection, so very high values can exhaust them.
+    /// Only file-backed data sets (`temp_dir`) use handles, detections on an in-memory data
+    /// set can run on any number of threads.
+    pub concurrency: Option<u16>,
+    /// Lets override evidence (see [`Evidence::add_override`]) replace detected values.
+    pub allow_overrides: bool,
+    /// Treats `property_names` as a core set and loads any other property on its first read
+    /// through [`ResultData::get_value_as_string`]. Has no effect if all properties are loaded.
+    ///
+    /// Loading a property reloads the whole data file with the extended property set and
+    /// repeats the detection, so the first read of every extra property is as costly as
+    /// creating a new manager. The previous data set stays in memory while results of
+    /// earlier detections are alive.
+    pub lazy_properties: bool,
+    /// Loads the data set with a temp file profile, which reads the data from a copy of the
+    /// data file created in this directory instead of keeping it all in memory. Also used by
+    /// an explicit `BalancedTemp` profile.
+    pub temp_dir: Option<PathBuf>,
+    /// Which evidence is used when both a User-Agent and client hints are supplied.
+    pub hint_policy: HintPolicy,
+    /// Spellings accepted when reading boolean property values.
+    pub bool_parsing: BoolParsing,
+    /// Treats the `Unknown` and `N/A` placeholders of the data file as absent values.
+    /// Empty values are absent regardless.
+    pub filter_unknown_values: bool,
+    /// Fails with `RequiredPropertyMissing` if any of `property_names` is not in the data file,
+    /// instead of loading the ones which are.
+    pub strict_properties: bool,
+    /// Keeps the values of list properties in the result once read, so that reading them
+    /// again doesn't walk the values collection.
+    pub memoize_lists: bool,
+    /// Skips evidence items which can't be passed to the C library, e.g. containing a NUL
+    /// byte, instead of failing the detection. See [`ResultData::warnings`].
+    pub lenient_evidence: bool,
+    /// Initial size in bytes of the buffer values are read into. Values which don't fit
+    /// grow the buffer, reading them again, so long values like `UserAgents` are cheaper to
+    /// read with a larger buffer.
+    pub value_buffer_size: usize,
+    /// Product name the data file must have, compared case-insensitively, e.g. `Enterprise`
+    /// to fail with `TierMismatch` instead of running on a `Lite` file.
+    pub require_tier: Option<String>,
+    /// Number of evidence sets whose results are kept by [`Manager::detect_cached_evidence`],
+    /// the least recently used one is evicted first. 0 disables the cache.
+    pub evidence_cache_size: usize,
+    /// How long browsers keep sending the client hints requested by
+    /// [`Manager::client_hint_response`].
+    pub client_hint_lifetime: Duration,
+    /// Largest difference of a hints-only match accepted by [`Manager::detect_progressive`]
+    /// before it falls back to the User-Agent. A negative value always falls back.
+    pub progressive_max_difference: i32,
+}
+
+/// Preset of the C library for how much of the data set is loaded into memory, from the
+/// `fiftyoneDegreesHash*Config` globals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PerformanceProfile {
+    /// Reads everything from the data file on demand, with no caches.
+    LowMemory,
+    /// Reads from the data file with caches of the most used items.
+    Balanced,
+    /// Like `Balanced`, reading from a copy of the data file in a temp directory.
+    BalancedTemp,
+    /// Loads the data set into memory.
+    #[default]
+    HighPerformance,
+    /// Loads the whole data file into memory, the slowest to start.
+    InMemory,
+}
+
+impl PerformanceProfile {
+    fn config(self) -> ConfigHash {
+        unsafe {
+            match self {
+                PerformanceProfile::LowMemory => bindings::fiftyoneDegreesHashLowMemoryConfig,
+                PerformanceProfile::Balanced => bindings::fiftyoneDegreesHashBalancedConfig,
+                PerformanceProfile::BalancedTemp => bindings::fiftyoneDegreesHashBalancedTempConfig,
+                PerformanceProfile::HighPerformance => {
+                    bindings::fiftyoneDegreesHashHighPerformanceConfig
+                }
+                PerformanceProfile::InMemory => bindings::fiftyoneDegreesHashInMemoryConfig,
+            }
+        }
+    }
+}
+
+/// Spellings of boolean property values accepted by [`ResultData::get_value_as_bool`],
+/// [`ResultData::capabilities`] and the other typed reads. Always case-insensitive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoolParsing {
+    /// Only `true` and `false`, as written by the data file.
+    Strict,
+    /// `true`/`false`, `1`/`0` and `yes`/`no`.
+    #[default]
+    Lenient,
+}
+
+impl BoolParsing {
+    pub fn parse(self, value: &str) -> Option<bool> {
+        let value = value.trim();
+        let is_any = |spellings: &[&str]| spellings.iter().any(|s| value.eq_ignore_ascii_case(s));
+        match self {
+            BoolParsing::Strict if is_any(&["true"]) => Some(true),
+            BoolParsing::Strict if is_any(&["false"]) => Some(false),
+            BoolParsing::Lenient if is_any(&["true", "1", "yes"]) => Some(true),
+            BoolParsing::Lenient if is_any(&["false", "0", "no"]) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Resolution of conflicts between the `user-agent` header and `sec-ch-*` client hints.
+///
+/// The Hash C library has no setting for the priority of evidence, so the policies other than
+/// `Combine` filter the evidence: the losing headers are dropped before the detection, for all
+/// properties including those only they could resolve.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HintPolicy {
+    /// Drops the User-Agent if any client hint is present, so properties the hints don't
+    /// describe, e.g. the hardware without `sec-ch-ua-model`, are not detected. `Combine`
+    /// keeps the User-Agent for them.
+    PreferHints,
+    /// Drops the client hints if a User-Agent is present.
+    PreferUserAgent,
+    /// Passes all evidence to the C library. Client hints win over the User-Agent for the
+    /// properties they describe, as the data file lists the `sec-ch-*` headers of a component
+    /// before its `user-agent` header and the C library uses the first header with a match.
+    #[default]
+    Combine,
+}
+
+impl HintPolicy {
+    /// Returns evidence without the items dropped by the policy, `None` if nothing is dropped.
+    fn apply(self, evidence: &Evidence) -> FiftyOneDegreesResult<Option<Evidence>> {
+        let is_header_matching = |prefix, key: &CString, matches: fn(&[u8]) -> bool| {
+            prefix == bindings::e_fiftyone_degrees_evidence_prefix_FIFTYONE_DEGREES_EVIDENCE_HTTP_HEADER_STRING
+                && matches(key.as_bytes())
+        };
+        let has = |matches| {
+            evidence
+                .evidence_data
+                .iter()
+                .any(|(prefix, key, _)| is_header_matching(*prefix, key, matches))
+        };
+
+        let dropped: fn(&[u8]) -> bool = match self {
+            HintPolicy::PreferHints if has(is_client_hint) => is_user_agent,
+            HintPolicy::PreferUserAgent if has(is_user_agent) => is_client_hint,
+            _ => return Ok(None),
+        };
+        let mut filtered = Evidence::new(evidence.evidence_data.len() as u32)?;
+        for (prefix, key, val) in &evidence.evidence_data {
+            if !is_header_matching(*prefix, key, dropped) {
+                filtered.add_with_prefix(*prefix, &key.to_string_lossy(), val.as_bytes())?;
+            }
+        }
+        Ok(Some(filtered))
+    }
+}
+
+fn is_user_agent(key: &[u8]) -> bool {
+    key.eq_ignore_ascii_case(b"user-agent")
+}
+
+fn is_client_hint(key: &[u8]) -> bool {
+    key.len() > 7 && key[..7].eq_ignore_ascii_case(b"sec-ch-")
+}
+
+impl ManagerConfig {
+    /// Creates a config loading all properties with the default concurrency.
+    pub fn new(data_file_path: impl AsRef<Path>) -> Self {
+        Self {
+            data_file_path: data_file_path.as_ref().to_path_buf(),
+            property_names: None,
+            performance_profile: None,
+            concurrency: None,
+            allow_overrides: false,
+            lazy_properties: false,
+            temp_dir: None,
+            hint_policy: HintPolicy::default(),
+            bool_parsing: BoolParsing::default(),
+            filter_unknown_values: true,
+            strict_properties: false,
+            memoize_lists: false,
+            lenient_evidence: false,
+            value_buffer_size: DEFAULT_VALUE_BUFFER_SIZE,
+            require_tier: None,
+            evidence_cache_size: DEFAULT_EVIDENCE_CACHE_SIZE,
+            client_hint_lifetime: DEFAULT_CLIENT_HINT_LIFETIME,
+            progressive_max_difference: DEFAULT_PROGRESSIVE_MAX_DIFFERENCE,
+        }
+    }
+
+    /// Creates a builder starting from the defaults of [`ManagerConfig::new`].
+    pub fn builder(data_file_path: impl AsRef<Path>) -> ManagerConfigBuilder {
+        ManagerConfigBuilder {
+            config: ManagerConfig::new(data_file_path),
+        }
+    }
+}
+
+/// Builder of a [`ManagerConfig`], see the config fields for the meaning of each option.
+pub struct ManagerConfigBuilder {
+    config: ManagerConfig,
+}
+
+impl ManagerConfigBuilder {
+    pub fn property_names(mut self, property_names: &[PropertyName]) -> Self {
+        self.config.property_names = Some(property_names.to_vec());
+        self
+    }
+
+    pub fn performance_profile(mut self, performance_profile: PerformanceProfile) -> Self {
+        self.config.performance_profile = Some(performance_profile);
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: u16) -> Self {
+        self.config.concurrency = Some(concurrency);
+        self
+    }
+
+    pub fn allow_overrides(mut self, allow_overrides: bool) -> Self {
+        self.config.allow_overrides = allow_overrides;
+        self
+    }
+
+    pub fn lazy_properties(mut self, lazy_properties: bool) -> Self {
+        self.config.lazy_properties = lazy_properties;
+        self
+    }
+
+    pub fn temp_dir(mut self, temp_dir: impl AsRef<Path>) -> Self {
+        self.config.temp_dir = Some(temp_dir.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn hint_policy(mut self, hint_policy: HintPolicy) -> Self {
+        self.config.hint_policy = hint_policy;
+        self
+    }
+
+    pub fn bool_parsing(mut self, bool_parsing: BoolParsing) -> Self {
+        self.config.bool_parsing = bool_parsing;
+        self
+    }
+
+    pub fn filter_unknown_values(mut self, filter_unknown_values: bool) -> Self {
+        self.config.filter_unknown_values = filter_unknown_values;
+        self
+    }
+
+    pub fn strict_properties(mut self, strict_properties: bool) -> Self {
+        self.config.strict_properties = strict_properties;
+        self
+    }
+
+    pub fn memoize_lists(mut self, memoize_lists: bool) -> Self {
+        self.config.memoize_lists = memoize_lists;
+        self
+    }
+
+    pub fn lenient_evidence(mut self, lenient_evidence: bool) -> Self {
+        self.config.lenient_evidence = lenient_evidence;
+        self
+    }
+
+    pub fn value_buffer_size(mut self, value_buffer_size: usize) -> Self {
+        self.config.value_buffer_size = value_buffer_size;
+        self
+    }
+
+    pub fn evidence_cache_size(mut self, evidence_cache_size: usize) -> Self {
+        self.config.evidence_cache_size = evidence_cache_size;
+        self
+    }
+
+    pub fn client_hint_lifetime(mut self, client_hint_lifetime: Duration) -> Self {
+        self.config.client_hint_lifetime = client_hint_lifetime;
+        self
+    }
+
+    pub fn progressive_max_difference(mut self, progressive_max_difference: i32) -> Self {
+        self.config.progressive_max_difference = progressive_max_difference;
+        self
+    }
+
+    pub fn require_tier(mut self, require_tier: &str) -> Self {
+        self.config.require_tier = Some(require_tier.to_string());
+        self
+    }
+
+    pub fn build(self) -> ManagerConfig {
+        self.config
+    }
 }
 
 pub struct Evidence {
     evidence_ptr: *mut bindings::fiftyoneDegreesEvidenceKeyValuePairArray,
-    evidence_data: Vec<(CString, CString)>,
+    evidence_data: Vec<(bindings::fiftyoneDegreesEvidencePrefix, CString, CString)>,
 }
 
 impl Drop for Evidence {
@@ -167,7 +701,7 @@ impl Drop for Evidence {
 }
 
 impl Evidence {
-    fn new(capacity: u32) -> FiftyOneDegreesResult<Self> {
+    pub fn new(capacity: u32) -> FiftyOneDegreesResult<Self> {
         let evidence_ptr = unsafe { bindings::fiftyoneDegreesEvidenceCreate(capacity) };
         if evidence_ptr.is_null() {
             return Err(UnsafeOperationError(String::from(
@@ -180,14 +714,60 @@ impl Evidence {
         })
     }
 
-    fn add(&mut self, key: &str, val: &str) -> FiftyOneDegreesResult<()> {
+    /// Adds a header value. The header name is lowercased, so evidence of every entry point
+    /// has the same keys whatever the spelling of the caller.
+    pub fn add(&mut self, key: &str, val: &str) -> FiftyOneDegreesResult<()> {
+        self.add_bytes(key, val.as_bytes())
+    }
+
+    /// Adds a header value given as raw bytes, for values which are not valid UTF-8.
+    ///
+    /// The bytes are passed to the C library as is, interior NUL bytes are rejected. Header
+    /// names are lowercased, as for [`Evidence::add`].
+    pub fn add_bytes(&mut self, key: &str, val: &[u8]) -> FiftyOneDegreesResult<()> {
+        self.add_with_prefix(
+            bindings::e_fiftyone_degrees_evidence_prefix_FIFTYONE_DEGREES_EVIDENCE_HTTP_HEADER_STRING,
+            &header_key(key),
+            val,
+        )
+    }
+
+    /// Adds an override for the property value, as collected by the 51Degrees JavaScript.
+    ///
+    /// Overrides only apply if the manager is created with `allow_overrides`.
+    pub fn add_override(&mut self, property_name: PropertyName, val: &str) -> FiftyOneDegreesResult<()> {
+        self.add_with_prefix(
+            bindings::e_fiftyone_degrees_evidence_prefix_FIFTYONE_DEGREES_EVIDENCE_QUERY,
+            &format!("51D_{}", property_name.to_str()),
+            val.as_bytes(),
+        )
+    }
+
+    /// Iterates over the added key/value pairs in insertion order.
+    ///
+    /// Values added with [`Evidence::add_bytes`] that are not valid UTF-8 are yielded as `"\u{FFFD}"`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.evidence_data.iter().map(|(_, key, val)| {
+            (
+                key.to_str().unwrap_or("\u{FFFD}"),
+                val.to_str().unwrap_or("\u{FFFD}"),
+            )
+        })
+    }
+
+    fn add_with_prefix(
+        &mut self,
+        prefix: bindings::fiftyoneDegreesEvidencePrefix,
+        key: &str,
+        val: &[u8],
+    ) -> FiftyOneDegreesResult<()> {
         let key_cstring = build_cstring(CStringKind::EvidenceKey, key)?;
-        let val_cstring = build_cstring(CStringKind::EvidenceValue, val)?;
+        let val_cstring = build_cstring_from_bytes(CStringKind::EvidenceValue, val)?;
 
         let added = unsafe {
             bindings::fiftyoneDegreesEvidenceAddString(
                 self.evidence_ptr,
-                bindings::e_fiftyone_degrees_evidence_prefix_FIFTYONE_DEGREES_EVIDENCE_HTTP_HEADER_STRING,
+                prefix,
                 key_cstring.as_ptr(),
                 val_cstring.as_ptr(),
             )
@@ -199,13 +779,245 @@ impl Evidence {
             )));
         }
 
-        self.evidence_data.push((key_cstring, val_cstring));
+        self.evidence_data.push((prefix, key_cstring, val_cstring));
         Ok(())
     }
 }
 
+/// Property value converted according to the property type declared in the data set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    Bool(bool),
+    Int(i32),
+    Double(f64),
+    Str(String),
+    List(Vec<String>),
+}
+
+/// How closely the evidence matched the data set, summed up over all results of a detection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchMetrics {
+    /// Sum of the differences of every result, 0 for an exact match
+    pub difference: i32,
+    /// Largest drift of substring positions among the results
+    pub drift: i32,
+    /// Sum of the graph iterations of every result
+    pub iterations: i32,
+}
+
+/// Graph search used by the Hash matcher to produce a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMethod {
+    /// No match was found
+    None,
+    /// Exact match with the performance graph
+    Performance,
+    /// Performance graph first, then the predictive graph
+    Combined,
+    /// Closest match with the predictive graph
+    Predictive,
+}
+
+/// Evidence which produced the result of [`Manager::detect_progressive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionPath {
+    /// The client hints alone matched confidently
+    Hints,
+    /// The hints didn't match confidently, the detection was repeated with the User-Agent
+    UserAgentFallback,
+}
+
+/// Role of an evidence item in a detection, see [`Manager::evidence_contribution`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contribution {
+    /// The match changes without the item
+    Decisive,
+    /// The item is a header used by the data set, but the match is the same without it
+    Corroborating,
+    /// The item is not used by the data set
+    Ignored,
+}
+
+/// Default of `ManagerConfig::progressive_max_difference`, exact matches only.
+const DEFAULT_PROGRESSIVE_MAX_DIFFERENCE: i32 = 0;
+
+/// Properties with a value per device or per request, skipped by
+/// [`ResultData::as_label_pairs`] as they would create a metric series per device.
+const HIGH_CARDINALITY_PROPERTIES: &[PropertyName] = &[
+    PropertyName::DeviceId,
+    PropertyName::Profiles,
+    PropertyName::UserAgents,
+    PropertyName::HardwareName,
+    PropertyName::HardwareModel,
+    PropertyName::HardwareModelVariants,
+    PropertyName::NativeDevice,
+    PropertyName::NativeModel,
+    PropertyName::NativeName,
+    PropertyName::Difference,
+    PropertyName::Drift,
+    PropertyName::Popularity,
+];
+
+/// Converts a property name to a Prometheus label name, e.g. `IsMobile` to `is_mobile`.
+fn to_label_name(property_name: &str) -> String {
+    let chars: Vec<char> = property_name.chars().collect();
+    let mut label = String::with_capacity(chars.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                label.push('_');
+            }
+        }
+        if c.is_ascii_alphanumeric() {
+            label.push(c.to_ascii_lowercase());
+        } else {
+            label.push('_');
+        }
+    }
+    if label.starts_with(|c: char| c.is_ascii_digit()) {
+        label.insert(0, '_');
+    }
+    label
+}
+
+/// Profile offset of a component that wasn't matched by a result.
+const NULL_PROFILE_OFFSET: u32 = u32::MAX;
+
+/// Property metadata read from the data set properties collection.
+#[derive(Debug, Clone, Copy)]
+struct PropertyMetadata {
+    value_type: u32,
+    is_list: bool,
+    component_index: u8,
+}
+
+fn parse_value<T: std::str::FromStr>(
+    property_name: PropertyName,
+    raw: String,
+    target: &'static str,
+) -> FiftyOneDegreesResult<T> {
+    raw.parse().map_err(|_| ValueParse {
+        property: property_name.to_str().to_string(),
+        raw,
+        target,
+    })
+}
+
+fn is_meaningful_value(value: &str) -> bool {
+    !value.is_empty() && value != "Unknown" && value != "N/A"
+}
+
+/// Initial size of the value buffer, see `ManagerConfig::value_buffer_size`.
+const DEFAULT_VALUE_BUFFER_SIZE: usize = 128;
+
+/// Download endpoint of the 51Degrees distributor, see [`Manager::update_url`].
+const DISTRIBUTOR_URL: &str = "https://distributor.51degrees.com/api/v2/download";
+
+/// Default of `ManagerConfig::client_hint_lifetime`, 30 days.
+const DEFAULT_CLIENT_HINT_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Client hints sent by browsers without being requested, left out of `Critical-CH`.
+const DEFAULT_CLIENT_HINTS: &[&str] = &["sec-ch-ua", "sec-ch-ua-mobile", "sec-ch-ua-platform"];
+
+/// Error of the data file operations of a manager created by [`Manager::from_fd`].
+const NO_SOURCE_FILE: FiftyOneDegreesError = AssertionError(
+    Operation::ReadDataFile,
+    "The manager was created from a file descriptor and has no data file",
+);
+
+/// Default of `ManagerConfig::evidence_cache_size`.
+const DEFAULT_EVIDENCE_CACHE_SIZE: usize = 1024;
+
+thread_local! {
+    /// Output buffer of value reads, reused by all reads on the thread
+    static VALUE_BUFFER: RefCell<Vec<i8>> = RefCell::new(vec![0; DEFAULT_VALUE_BUFFER_SIZE]);
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of times a value didn't fit in the value buffer on the thread
+    static VALUE_BUFFER_RESIZES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Returns the number of times a value read on this thread didn't fit in the value buffer.
+#[cfg(test)]
+pub(crate) fn value_buffer_resizes() -> usize {
+    VALUE_BUFFER_RESIZES.with(|resizes| resizes.get())
+}
+
+/// Runs `read` with the thread's value buffer, grown to at least `min_size`.
+///
+/// Every read writes a NUL terminated value, so leftovers of earlier reads are never returned.
+fn with_value_buffer<T>(min_size: usize, read: impl FnOnce(&mut Vec<i8>) -> T) -> T {
+    VALUE_BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+        Ok(mut buffer) => {
+            if buffer.len() < min_size {
+                buffer.resize(min_size, 0);
+            }
+            read(&mut buffer)
+        }
+        // A read nested in another one gets a buffer of its own
+        Err(_) => read(&mut vec![0; min_size]),
+    })
+}
+
+/// Calls `read` to write a value into the buffer, which returns the length the value requires.
+/// If the value didn't fit, the buffer is grown to that length and `read` is called once more.
+fn read_growing(
+    buf: &mut Vec<i8>,
+    mut read: impl FnMut(&mut Vec<i8>) -> FiftyOneDegreesResult<usize>,
+) -> FiftyOneDegreesResult<()> {
+    let required_len = read(buf)?;
+    if required_len < buf.len() {
+        return Ok(());
+    }
+    #[cfg(test)]
+    VALUE_BUFFER_RESIZES.with(|resizes| resizes.set(resizes.get() + 1));
+    buf.resize(required_len + 1, 0);
+    let required_len = read(buf)?;
+    if required_len < buf.len() {
+        return Ok(());
+    }
+    Err(UnsafeOperationError(format!(
+        "Buffer too small: value requires {} bytes, got {}",
+        required_len + 1,
+        buf.len()
+    )))
+}
+
+/// Data needed to repeat a detection once a lazily loaded property is requested.
+struct LazyReload {
+    lazy: Arc<LazyProperties>,
+    evidence_data: Vec<(bindings::fiftyoneDegreesEvidencePrefix, CString, CString)>,
+    overrides_capacity: u32,
+    reloaded: RefCell<Option<Box<ResultData>>>,
+}
+
 pub struct ResultData {
     results_ptr: *mut bindings::fiftyoneDegreesResultsHash,
+    lazy: Option<LazyReload>,
+    /// Separator of list values, reused by every string read
+    separator: CString,
+    bool_parsing: BoolParsing,
+    filter_unknown_values: bool,
+    /// Values of the list properties read so far, `None` unless `memoize_lists` is set
+    list_cache: Option<RefCell<HashMap<PropertyName, Vec<String>>>>,
+    /// Errors of the evidence items skipped with `lenient_evidence`
+    warnings: Vec<FiftyOneDegreesError>,
+    /// Properties which can be read, all loaded ones if `None`
+    exposed: Option<Vec<PropertyName>>,
+    /// Properties the manager was created with, all properties if `None`
+    requested: Option<Arc<[PropertyName]>>,
+    value_buffer_size: usize,
+    /// Manager the results were created on, kept alive by the results of a
+    /// `ReloadableManager` since the C library frees its data set with it
+    #[cfg(feature = "arc-swap")]
+    pub(crate) manager: Option<Arc<Manager>>,
 }
 
 impl Drop for ResultData {
@@ -216,214 +1028,2296 @@ impl Drop for ResultData {
     }
 }
 
+/// Creates results on the manager, which then hold a reference to its current data set.
+fn create_results(
+    manager_ptr: *mut bindings::fiftyoneDegreesResourceManager,
+    overrides_capacity: u32,
+) -> *mut bindings::fiftyoneDegreesResultsHash {
+    unsafe {
+        bindings::fiftyoneDegreesResultsHashCreate(
+            manager_ptr,
+            // TODO: These values must be tuned according to passed evidence (for example we can do batch processing)
+            1, // UA capacity
+            overrides_capacity, // 0 if overrides are disabled
+        )
+    }
+}
+
 impl ResultData {
+    /// Detects from the evidence into results created by [`create_results`], taking
+    /// ownership of them.
     fn new(
-        manager_ptr: *mut bindings::fiftyoneDegreesResourceManager,
+        results_ptr: *mut bindings::fiftyoneDegreesResultsHash,
         evidence_ptr: *mut bindings::fiftyoneDegreesEvidenceKeyValuePairArray,
     ) -> FiftyOneDegreesResult<Self> {
-        let results_ptr = unsafe {
-            bindings::fiftyoneDegreesResultsHashCreate(
-                manager_ptr,
-                // TODO: These values must be tuned according to passed evidence (for example we can do batch processing)
-                1, // UA capacity
-                0, // overrides disabled
-            )
-        };
         if results_ptr.is_null() {
             return Err(UnsafeOperationError(String::from(
                 "Failed to create result object: got null",
             )));
         };
-        let exception = null_mut();
+        let separator = match build_cstring(CStringKind::HashResultSeparator, ", ") {
+            Ok(separator) => separator,
+            Err(e) => {
+                unsafe { bindings::fiftyoneDegreesResultsHashFree(results_ptr) };
+                return Err(e);
+            }
+        };
+        let mut exception = new_exception();
+        let exception = &raw mut exception;
         unsafe {
             bindings::fiftyoneDegreesResultsHashFromEvidence(results_ptr, evidence_ptr, exception)
         }
+        let results = Self {
+            results_ptr,
+            lazy: None,
+            separator,
+            bool_parsing: BoolParsing::default(),
+            filter_unknown_values: true,
+            list_cache: None,
+            warnings: Vec::new(),
+            exposed: None,
+            requested: None,
+            value_buffer_size: DEFAULT_VALUE_BUFFER_SIZE,
+            #[cfg(feature = "arc-swap")]
+            manager: None,
+        };
         verify_exception(exception, Operation::ApplyEvidence)?;
-        Ok(Self { results_ptr })
+        // Reading values of an empty results array isn't guarded by the C library
+        if unsafe { (*results_ptr).count } == 0 {
+            return Err(AssertionError(
+                Operation::ApplyEvidence,
+                "Evidence produced no results, it must contain at least one relevant header",
+            ));
+        }
+        Ok(results)
     }
 
-    pub fn get_value_as_string(
+    /// Runs `read` on results containing the property, loading it first if needed.
+    ///
+    /// Returns `None` if the property should be read from `self`, which is always the case
+    /// unless the manager was created with `lazy_properties`.
+    fn read_lazily<T>(
         &self,
         property_name: PropertyName,
-    ) -> FiftyOneDegreesResult<Option<String>> {
-        //let value = self.get_value(property_name)?;
-        //Ok(value.map(|s| s.to_string()))
-        let property_name_cstring =
-            build_cstring(CStringKind::PropertyName, property_name.to_str())?;
-        let mut buf = vec![0_i8; 64];
-        let sep = build_cstring(CStringKind::HashResultSeparator, ", ")?;
-        let exception = null_mut();
-
-        let required_len = unsafe {
-            bindings::fiftyoneDegreesResultsHashGetValuesString(
-                self.results_ptr,
-                property_name_cstring.as_ptr(),
-                buf.as_mut_ptr(),
-                buf.len(),
-                sep.as_ptr(),
-                exception,
-            )
+        read: impl FnOnce(&ResultData) -> FiftyOneDegreesResult<T>,
+    ) -> FiftyOneDegreesResult<Option<T>> {
+        let Some(lazy) = &self.lazy else {
+            return Ok(None);
         };
-
-        verify_exception(exception, Operation::ReadProperty)?;
-
-        if required_len > buf.len() {
-            return Err(UnsafeOperationError(format!(
-                "Buffer too small for property: {}, expected: {}, actual: {}",
-                property_name,
-                required_len,
-                buf.len()
-            )));
+        if self.required_property_index(property_name)? >= 0 {
+            return Ok(None);
         }
 
-        if buf.len() == 0 {
-            return Err(UnsafeOperationError(format!(
-                "No data written for property: {}",
-                property_name
-            )));
+        let mut reloaded = lazy.reloaded.borrow_mut();
+        let is_loaded = match reloaded.as_ref() {
+            Some(results) => results.required_property_index(property_name)? >= 0,
+            None => false,
+        };
+        if !is_loaded {
+            lazy.lazy.load(property_name)?;
+            let mut evidence = Evidence::new(lazy.evidence_data.len() as u32)?;
+            for (prefix, key, val) in &lazy.evidence_data {
+                evidence.add_with_prefix(*prefix, &key.to_string_lossy(), val.as_bytes())?;
+            }
+            let Some(results_ptr) = lazy.lazy.create_results(lazy.overrides_capacity) else {
+                return Ok(None);
+            };
+            let mut results = ResultData::new(results_ptr, evidence.evidence_ptr)?;
+            results.bool_parsing = self.bool_parsing;
+            results.filter_unknown_values = self.filter_unknown_values;
+            results.value_buffer_size = self.value_buffer_size;
+            *reloaded = Some(Box::new(results));
         }
 
-        let val_str = unsafe { CStr::from_ptr(buf.as_ptr()) }
-            .to_string_lossy()
-            .to_string();
+        match reloaded.as_deref() {
+            // Properties absent from the data file stay unloaded after the reload
+            Some(results) if results.required_property_index(property_name)? >= 0 => {
+                read(results).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
 
-        Ok(Some(val_str).filter(|s| !s.is_empty() && s != "Unknown" && s != "N/A"))
+    /// Checks whether the property can be read, see [`Manager::detect_with_properties`].
+    fn is_exposed(&self, property_name: &str) -> bool {
+        self.exposed.as_ref().is_none_or(|exposed| {
+            exposed
+                .iter()
+                .any(|exposed| exposed.to_str().eq_ignore_ascii_case(property_name))
+        })
     }
 
-    pub fn get_value(&self, property_name: &str) -> FiftyOneDegreesResult<Option<Cow<'_, str>>> {
-        let property_name_cstring = build_cstring(CStringKind::PropertyName, property_name)?;
-        let mut buf = vec![0_i8; 128];
-        let sep = build_cstring(CStringKind::HashResultSeparator, ", ")?;
-        let exception = null_mut();
+    fn check_exposed(&self, property_name: PropertyName) -> FiftyOneDegreesResult<()> {
+        if !self.is_exposed(property_name.to_str()) {
+            return Err(PropertyNotExposed(property_name.to_str().to_string()));
+        }
+        Ok(())
+    }
 
-        let required_len = unsafe {
-            bindings::fiftyoneDegreesResultsHashGetValuesString(
-                self.results_ptr,
+    /// Returns the index of the property within the loaded ones, negative if not loaded.
+    fn required_property_index(&self, property_name: PropertyName) -> FiftyOneDegreesResult<i32> {
+        let property_name_cstring = property_name.to_cstring()?;
+        let index = unsafe {
+            bindings::fiftyoneDegreesPropertiesGetRequiredPropertyIndexFromName(
+                (*self.data_set_ptr()).b.b.available,
                 property_name_cstring.as_ptr(),
-                buf.as_mut_ptr(),
-                buf.len(),
-                sep.as_ptr(),
-                exception,
             )
         };
+        Ok(index)
+    }
 
-        verify_exception(exception, Operation::ReadProperty)?;
-
-        if required_len > buf.len() {
-            return Err(UnsafeOperationError(format!(
-                "Buffer too small for property: {}, expected: {}, actual: {}",
-                property_name,
-                required_len,
-                buf.len()
-            )));
+    pub fn get_value_as_string(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Option<String>> {
+        self.check_exposed(property_name)?;
+        if let Some(value) =
+            self.read_lazily(property_name, |results| results.get_value_as_string(property_name))?
+        {
+            return Ok(value);
         }
+        //let value = self.get_value(property_name)?;
+        //Ok(value.map(|s| s.to_string()))
+        let property_name_cstring = property_name.to_cstring()?;
+        let val_str = self.read_values_string(&property_name_cstring)?;
 
-        if buf.len() == 0 {
-            return Err(UnsafeOperationError(format!(
-                "No data written for property: {}",
-                property_name
-            )));
+        Ok(Some(val_str).filter(|s| self.is_present(s)))
+    }
+
+    /// Reads every value of a list property such as `HardwareModelVariants` as its own
+    /// element, instead of joined by `, ` as in [`ResultData::get_value_as_string`]. Empty if
+    /// the property has no values or is not loaded.
+    pub fn get_values(&self, property_name: PropertyName) -> FiftyOneDegreesResult<Vec<String>> {
+        self.check_exposed(property_name)?;
+        if let Some(values) =
+            self.read_lazily(property_name, |results| results.get_values(property_name))?
+        {
+            return Ok(values);
         }
+        self.read_values(property_name)
+    }
 
-        let val_str = unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy();
+    /// Reads the property as a boolean, spelled as accepted by the manager's [`BoolParsing`].
+    pub fn get_value_as_bool(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Option<bool>> {
+        self.get_value_as_string(property_name)?
+            .map(|raw| self.parse_bool(property_name, raw))
+            .transpose()
+    }
 
-        Ok(Some(val_str).filter(|s| !s.is_empty()))
+    /// Reads the property as an integer, failing with `ValueParse` if the value isn't one.
+    pub fn get_value_as_i32(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Option<i32>> {
+        self.get_value_as_string(property_name)?
+            .map(|raw| parse_value(property_name, raw, "integer"))
+            .transpose()
     }
-}
 
-pub struct Manager {
-    instance: ResourceManager,
-}
+    /// Reads the property as a floating point number, failing with `ValueParse` if the value
+    /// isn't one.
+    pub fn get_value_as_f64(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Option<f64>> {
+        self.get_value_as_string(property_name)?
+            .map(|raw| parse_value(property_name, raw, "double"))
+            .transpose()
+    }
 
-impl Drop for Manager {
+    fn parse_bool(&self, property_name: PropertyName, raw: String) -> FiftyOneDegreesResult<bool> {
+        self.bool_parsing.parse(&raw).ok_or_else(|| ValueParse {
+            property: property_name.to_str().to_string(),
+            raw,
+            target: "boolean",
+        })
+    }
+
+    /// Calls `f` with the name and value of every loaded property which has a value.
+    ///
+    /// Values are read into a single reused buffer and passed as borrowed strings, so no
+    /// allocation is made per property. Properties loaded lazily later are not included.
+    pub fn for_each_value(&self, f: &mut dyn FnMut(&str, &str)) -> FiftyOneDegreesResult<()> {
+        if self.is_cleared() {
+            return Ok(());
+        }
+        let available = unsafe { (*self.data_set_ptr()).b.b.available };
+        let count = unsafe { (*available).count } as i32;
+        let mut buf = vec![0_i8; self.value_buffer_size.max(256)];
+        for required_index in 0..count {
+            let name = unsafe {
+                bindings::fiftyoneDegreesPropertiesGetNameFromRequiredIndex(available, required_index)
+            };
+            if name.is_null() {
+                continue;
+            }
+            let name = unsafe { CStr::from_ptr(std::ptr::addr_of!((*name).value)) }.to_string_lossy();
+            if !self.is_exposed(&name) {
+                continue;
+            }
+
+            read_growing(&mut buf, |buf| {
+                let mut exception = new_exception();
+                let exception = &raw mut exception;
+                let required_len = unsafe {
+                    bindings::fiftyoneDegreesResultsHashGetValuesStringByRequiredPropertyIndex(
+                        self.results_ptr,
+                        required_index,
+                        buf.as_mut_ptr(),
+                        buf.len(),
+                        self.separator.as_ptr(),
+                        exception,
+                    )
+                };
+                verify_exception(exception, Operation::ReadProperty)?;
+                Ok(required_len)
+            })?;
+
+            let value = unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy();
+            if self.is_present(&value) {
+                f(&name, &value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether the request comes from an AI agent or crawler, e.g. a scraper
+    /// collecting training data for a LLM. Reads `IsArtificialIntelligence`.
+    pub fn is_ai_agent(&self) -> FiftyOneDegreesResult<Option<bool>> {
+        self.get_value_as_bool(PropertyName::IsArtificialIntelligence)
+    }
+
+    /// Reads `NativeBrand`, `NativeModel`, `NativeName` and `NativePlatform` together,
+    /// `None` if all of them are absent.
+    pub fn native_device(&self) -> FiftyOneDegreesResult<Option<NativeDevice>> {
+        let native = NativeDevice {
+            brand: self.get_value_as_string(PropertyName::NativeBrand)?,
+            model: self.get_value_as_string(PropertyName::NativeModel)?,
+            name: self.get_value_as_string(PropertyName::NativeName)?,
+            platform: self.get_value_as_string(PropertyName::NativePlatform)?,
+        };
+        Ok((native != NativeDevice::default()).then_some(native))
+    }
+
+    /// Returns the family of the platform, `Other` with an empty name if it's unknown.
+    pub fn platform_family(&self) -> FiftyOneDegreesResult<PlatformFamily> {
+        Ok(self
+            .read_value_with(PropertyName::PlatformName, &mut PlatformFamily::from_value)?
+            .unwrap_or(PlatformFamily::Other(String::new())))
+    }
+
+    /// Reads the popularity ranks, failing with `ValueParse` if a rank isn't an integer.
+    pub fn ranks(&self) -> FiftyOneDegreesResult<Ranks> {
+        let rank = |property_name| {
+            self.get_value_as_string(property_name)?
+                .map(|raw| parse_value(property_name, raw, "integer"))
+                .transpose()
+        };
+        Ok(Ranks {
+            browser: rank(PropertyName::BrowserRank)?,
+            platform: rank(PropertyName::PlatformRank)?,
+            hardware: rank(PropertyName::HardwareRank)?,
+        })
+    }
+
+    /// Reads `ReleaseYear`, the year the device was released or first seen by 51Degrees.
+    pub fn release_year(&self) -> FiftyOneDegreesResult<Option<u16>> {
+        self.get_year(PropertyName::ReleaseYear)
+    }
+
+    /// Reads `BrowserReleaseYear`, the year the browser version was released. `None` if the
+    /// data file doesn't have the property or the browser isn't detected.
+    pub fn browser_release_year(&self) -> FiftyOneDegreesResult<Option<u16>> {
+        self.get_year(PropertyName::BrowserReleaseYear)
+    }
+
+    /// Reads `PlatformReleaseYear`, the year the operating system version was released. `None`
+    /// if the data file doesn't have the property or the platform isn't detected.
+    pub fn platform_release_year(&self) -> FiftyOneDegreesResult<Option<u16>> {
+        self.get_year(PropertyName::PlatformReleaseYear)
+    }
+
+    /// Reads a year, failing with `ValueParse` if it isn't within `1990..=2100`, which
+    /// points to a corrupt data file.
+    fn get_year(&self, property_name: PropertyName) -> FiftyOneDegreesResult<Option<u16>> {
+        self.get_value_as_string(property_name)?
+            .map(|raw| match raw.parse::<u16>() {
+                Ok(year) if (1990..=2100).contains(&year) => Ok(year),
+                _ => Err(ValueParse {
+                    property: property_name.to_str().to_string(),
+                    raw,
+                    target: "year between 1990 and 2100",
+                }),
+            })
+            .transpose()
+    }
+
+    /// Returns the screen resolution, `None` if the width or the height is absent.
+    pub fn screen_resolution(&self) -> FiftyOneDegreesResult<Option<Resolution>> {
+        let width = self.get_value_as_string(PropertyName::ScreenPixelsWidth)?;
+        let height = self.get_value_as_string(PropertyName::ScreenPixelsHeight)?;
+        let (Some(width), Some(height)) = (width, height) else {
+            return Ok(None);
+        };
+        Ok(Some(Resolution {
+            width: parse_value(PropertyName::ScreenPixelsWidth, width, "integer")?,
+            height: parse_value(PropertyName::ScreenPixelsHeight, height, "integer")?,
+        }))
+    }
+
+    /// Returns the emulation state of the browser.
+    ///
+    /// Missing flags count as `false`. Fails if both flags are set, which isn't possible
+    /// with a consistent data file.
+    pub fn emulation(&self) -> FiftyOneDegreesResult<Emulation> {
+        let desktop = self.get_value_as_bool(PropertyName::IsEmulatingDesktop)?;
+        let device = self.get_value_as_bool(PropertyName::IsEmulatingDevice)?;
+        match (desktop.unwrap_or(false), device.unwrap_or(false)) {
+            (false, false) => Ok(Emulation::None),
+            (true, false) => Ok(Emulation::Desktop),
+            (false, true) => Ok(Emulation::Device),
+            (true, true) => Err(AssertionError(
+                Operation::ReadProperty,
+                "IsEmulatingDesktop and IsEmulatingDevice are both true",
+            )),
+        }
+    }
+
+    /// Returns the profile IDs of the hardware, platform, browser and crawler components,
+    /// parsed from the `DeviceId` property which the manager must load.
+    pub fn device_id_components(&self) -> FiftyOneDegreesResult<[u32; 4]> {
+        let raw = self
+            .get_value_as_string(PropertyName::DeviceId)?
+            .ok_or(AssertionError(
+                Operation::ReadProperty,
+                "DeviceId has no value, it must be loaded by the manager",
+            ))?;
+        let parse_error = |raw: &str| ValueParse {
+            property: PropertyName::DeviceId.to_str().to_string(),
+            raw: raw.to_string(),
+            target: "four profile IDs",
+        };
+
+        let mut components = [0_u32; 4];
+        let mut parts = raw.split('-');
+        for component in &mut components {
+            *component = parts
+                .next()
+                .and_then(|part| part.parse().ok())
+                .ok_or_else(|| parse_error(&raw))?;
+        }
+        if parts.next().is_some() {
+            return Err(parse_error(&raw));
+        }
+        Ok(components)
+    }
+
+    /// Eagerly reads the given properties into an owned [`DetectionSnapshot`].
+    pub fn snapshot(
+        &self,
+        property_names: &[PropertyName],
+    ) -> FiftyOneDegreesResult<DetectionSnapshot> {
+        let values = property_names
+            .iter()
+            .map(|&name| Ok((name, self.get_value_as_string(name)?)))
+            .collect::<FiftyOneDegreesResult<Vec<_>>>()?;
+        Ok(DetectionSnapshot { values })
+    }
+
+    /// Reads every loaded property into an immutable [`ResultReader`] which can be shared
+    /// between threads, unlike the result itself.
+    ///
+    /// Creating the reader is the synchronization point: it must not race with other reads
+    /// of this result, reads of the reader can then run concurrently. Properties without a
+    /// value and properties loaded lazily later are not included.
+    pub fn reader(&self) -> FiftyOneDegreesResult<ResultReader> {
+        let mut values = HashMap::new();
+        self.for_each_value(&mut |name, value| {
+            values.insert(name.to_string(), value.to_string());
+        })?;
+        Ok(ResultReader { values })
+    }
+
+    /// Returns the properties as Prometheus label pairs, with names converted to snake case,
+    /// e.g. `IsMobile` to `is_mobile`. Absent properties are skipped, as well as properties
+    /// with a value per device like `DeviceId` or `HardwareModel`.
+    pub fn as_label_pairs(
+        &self,
+        property_names: &[PropertyName],
+    ) -> FiftyOneDegreesResult<Vec<(String, String)>> {
+        let mut labels = Vec::with_capacity(property_names.len());
+        for &property_name in property_names {
+            if HIGH_CARDINALITY_PROPERTIES.contains(&property_name) {
+                continue;
+            }
+            if let Some(value) = self.get_value_as_string(property_name)? {
+                labels.push((to_label_name(property_name.to_str()), value));
+            }
+        }
+        Ok(labels)
+    }
+
+    /// Returns the requested properties which have no value for this result, e.g. to find
+    /// properties which are never worth loading. If the manager loads all properties, the
+    /// known properties of the data set are checked.
+    pub fn unused_requested(&self) -> FiftyOneDegreesResult<Vec<PropertyName>> {
+        let requested: Vec<PropertyName> = match &self.requested {
+            Some(property_names) => property_names.to_vec(),
+            None => KNOWN_PROPERTIES.iter().map(|known| known.property).collect(),
+        };
+        let mut unused = Vec::new();
+        for property_name in requested {
+            if !self.is_exposed(property_name.to_str()) {
+                continue;
+            }
+            if self.requested.is_none() && self.required_property_index(property_name)? < 0 {
+                continue;
+            }
+            if self.get_value_as_string(property_name)?.is_none() {
+                unused.push(property_name);
+            }
+        }
+        Ok(unused)
+    }
+
+    /// Resets the result to an empty state, with no value for any property and no match
+    /// metrics, so nothing of the detection can be read from it anymore.
+    pub fn clear(&mut self) {
+        // The items stay allocated until the results are freed, but are never read again
+        unsafe { (*self.results_ptr).count = 0 };
+        self.lazy = None;
+        if let Some(list_cache) = &self.list_cache {
+            list_cache.borrow_mut().clear();
+        }
+        self.warnings.clear();
+    }
+
+    /// Checks whether the result was reset by [`ResultData::clear`]. Reading the values
+    /// of an empty results array isn't guarded by the C library.
+    fn is_cleared(&self) -> bool {
+        unsafe { (*self.results_ptr).count == 0 }
+    }
+
+    /// Returns the errors of the evidence items skipped by a manager created with
+    /// `lenient_evidence`, empty if every item was used.
+    pub fn warnings(&self) -> &[FiftyOneDegreesError] {
+        &self.warnings
+    }
+
+    /// Reads the properties in the order given, `None` for absent values, e.g. to write
+    /// aligned CSV rows.
+    pub fn values_in_order(
+        &self,
+        property_names: &[PropertyName],
+    ) -> FiftyOneDegreesResult<Vec<Option<String>>> {
+        property_names
+            .iter()
+            .map(|&name| self.get_value_as_string(name))
+            .collect()
+    }
+
+    /// Maps properties to response headers, e.g. for an edge worker setting detected values
+    /// on the response. Returns `(header_name, value)` pairs, skipping absent values.
+    pub fn to_response_headers(
+        &self,
+        mapping: &[(PropertyName, &str)],
+    ) -> FiftyOneDegreesResult<Vec<(String, String)>> {
+        let mut headers = Vec::with_capacity(mapping.len());
+        for &(property_name, header_name) in mapping {
+            if let Some(value) = self.get_value_as_string(property_name)? {
+                headers.push((header_name.to_string(), value));
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Reads the given properties converted to the type declared by the data set metadata.
+    ///
+    /// Properties without a value, or not loaded by the manager, are skipped.
+    pub fn get_typed(
+        &self,
+        property_names: &[PropertyName],
+    ) -> FiftyOneDegreesResult<Vec<(PropertyName, TypedValue)>> {
+        let mut values = Vec::with_capacity(property_names.len());
+        for &property_name in property_names {
+            let Some(metadata) = self.property_metadata(property_name)? else {
+                continue;
+            };
+            if metadata.is_list {
+                let list = self.read_values(property_name)?;
+                if !list.is_empty() {
+                    values.push((property_name, TypedValue::List(list)));
+                }
+                continue;
+            }
+            let Some(raw) = self.get_value_as_string(property_name)? else {
+                continue;
+            };
+            let value = match metadata.value_type {
+                bindings::e_fiftyone_degrees_property_value_type_FIFTYONE_DEGREES_PROPERTY_VALUE_TYPE_BOOLEAN => {
+                    TypedValue::Bool(self.parse_bool(property_name, raw)?)
+                }
+                bindings::e_fiftyone_degrees_property_value_type_FIFTYONE_DEGREES_PROPERTY_VALUE_TYPE_INTEGER => {
+                    TypedValue::Int(parse_value(property_name, raw, "integer")?)
+                }
+                bindings::e_fiftyone_degrees_property_value_type_FIFTYONE_DEGREES_PROPERTY_VALUE_TYPE_DOUBLE
+                | bindings::e_fiftyone_degrees_property_value_type_FIFTYONE_DEGREES_PROPERTY_VALUE_TYPE_SINGLE_PRECISION_FLOAT => {
+                    TypedValue::Double(parse_value(property_name, raw, "double")?)
+                }
+                _ => TypedValue::Str(raw),
+            };
+            values.push((property_name, value));
+        }
+        Ok(values)
+    }
+
+    /// Checks whether the property value came from override evidence rather than the data set.
+    pub fn is_overridden(&self, property_name: PropertyName) -> FiftyOneDegreesResult<bool> {
+        let overrides = unsafe { (*self.results_ptr).b.overrides };
+        if overrides.is_null() || self.is_cleared() {
+            return Ok(false);
+        }
+
+        let required_index = self.required_property_index(property_name)?;
+        if required_index < 0 {
+            return Ok(false);
+        }
+        if unsafe {
+            bindings::fiftyoneDegreesOverrideHasValueForRequiredPropertyIndex(
+                overrides,
+                required_index as u32,
+            )
+        } {
+            return Ok(true);
+        }
+
+        // The whole profile of the property component can be overridden as well
+        let Some(metadata) = self.property_metadata(property_name)? else {
+            return Ok(false);
+        };
+        let results = unsafe { *self.results_ptr };
+        let overridden = (0..results.count as usize).any(|i| {
+            let result = unsafe { *results.items.add(i) };
+            !result.profileIsOverriden.is_null()
+                && unsafe { *result.profileIsOverriden.add(metadata.component_index as usize) }
+        });
+        Ok(overridden)
+    }
+
+    /// Returns the match metrics of the detection.
+    pub fn match_metrics(&self) -> MatchMetrics {
+        self.result_items().fold(
+            MatchMetrics {
+                difference: 0,
+                drift: 0,
+                iterations: 0,
+            },
+            |metrics, result| MatchMetrics {
+                difference: metrics.difference + result.difference,
+                drift: metrics.drift.max(result.drift),
+                iterations: metrics.iterations + result.iterations,
+            },
+        )
+    }
+
+    /// Returns the method used to match the first result, which provides the values of
+    /// single header evidence.
+    pub fn method(&self) -> FiftyOneDegreesResult<MatchMethod> {
+        let Some(result) = self.result_items().next() else {
+            return Ok(MatchMethod::None);
+        };
+        match result.method {
+            bindings::fiftyone_degrees_hash_match_method_FIFTYONE_DEGREES_HASH_MATCH_METHOD_NONE => {
+                Ok(MatchMethod::None)
+            }
+            bindings::fiftyone_degrees_hash_match_method_FIFTYONE_DEGREES_HASH_MATCH_METHOD_PERFORMANCE => {
+                Ok(MatchMethod::Performance)
+            }
+            bindings::fiftyone_degrees_hash_match_method_FIFTYONE_DEGREES_HASH_MATCH_METHOD_COMBINED => {
+                Ok(MatchMethod::Combined)
+            }
+            bindings::fiftyone_degrees_hash_match_method_FIFTYONE_DEGREES_HASH_MATCH_METHOD_PREDICTIVE => {
+                Ok(MatchMethod::Predictive)
+            }
+            method => Err(UnsafeOperationError(format!("Unknown match method: {}", method))),
+        }
+    }
+
+    /// Returns the number of graph nodes matched while evaluating the evidence, summed over
+    /// all results. Unusually high counts together with long detection times point at
+    /// evidence which is expensive to evaluate.
+    pub fn nodes_evaluated(&self) -> i32 {
+        self.result_items().map(|result| result.matchedNodes).sum()
+    }
+
+    /// Returns a 0-1 confidence for the component with the given name, e.g. `HardwarePlatform`.
+    ///
+    /// Derived from the difference and drift of the result which matched the component
+    /// against the maximum difference and drift the data set is configured to allow.
+    /// `None` if the component doesn't exist or wasn't matched.
+    pub fn component_confidence(&self, component: &str) -> FiftyOneDegreesResult<Option<f32>> {
+        let Some(component_index) = self.component_index(component)? else {
+            return Ok(None);
+        };
+        let matched = self.result_items().find(|result| {
+            !result.profileOffsets.is_null()
+                && unsafe { *result.profileOffsets.add(component_index) } != NULL_PROFILE_OFFSET
+        });
+        let Some(result) = matched else {
+            return Ok(None);
+        };
+
+        let config = unsafe { (*self.data_set_ptr()).config };
+        let score = |value: i32, threshold: i32| {
+            if threshold <= 0 {
+                if value <= 0 { 1.0 } else { 0.0 }
+            } else {
+                1.0 - (value as f32 / threshold as f32).clamp(0.0, 1.0)
+            }
+        };
+        let confidence =
+            (score(result.difference, config.difference) + score(result.drift, config.drift)) / 2.0;
+        Ok(Some(confidence))
+    }
+
+    /// Returns a 64-bit hash of the profiles matched for every component.
+    ///
+    /// Evidence resolving to the same device gives the same fingerprint regardless of its
+    /// formatting. Profiles are identified by their position in the data file, so the
+    /// fingerprint is only stable for the same data file.
+    pub fn fingerprint(&self) -> FiftyOneDegreesResult<u64> {
+        // FNV-1a, unlike the std hashers it is guaranteed to stay the same across releases
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let component_count = unsafe { (*self.data_set_ptr()).componentsList.count } as usize;
+        let mut hash = FNV_OFFSET_BASIS;
+        for component_index in 0..component_count {
+            let profile_offset = self
+                .result_items()
+                .filter(|result| !result.profileOffsets.is_null())
+                .map(|result| unsafe { *result.profileOffsets.add(component_index) })
+                .find(|&offset| offset != NULL_PROFILE_OFFSET)
+                .unwrap_or(NULL_PROFILE_OFFSET);
+            for byte in profile_offset.to_le_bytes() {
+                hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        }
+        Ok(hash)
+    }
+
+    /// Returns the names of the components matched to a profile other than their default one,
+    /// e.g. `HardwarePlatform` or `BrowserUA`, to report partial detections where only some
+    /// components were identified.
+    pub fn resolved_components(&self) -> FiftyOneDegreesResult<Vec<String>> {
+        let data_set = self.data_set_ptr();
+        let components = unsafe { (*data_set).componentsList };
+        let mut resolved = Vec::new();
+        for component_index in 0..components.count as usize {
+            let component_ptr = unsafe { (*components.items.add(component_index)).data.ptr }
+                as *const bindings::fiftyoneDegreesComponent;
+            let default_offset = unsafe { (*component_ptr).defaultProfileOffset } as u32;
+            let is_resolved = self
+                .result_items()
+                .filter(|result| !result.profileOffsets.is_null())
+                .map(|result| unsafe { *result.profileOffsets.add(component_index) })
+                .any(|offset| offset != NULL_PROFILE_OFFSET && offset != default_offset);
+            if !is_resolved {
+                continue;
+            }
+            let name_offset = unsafe { (*component_ptr).nameOffset };
+            if let Some(name) = data_set_string(data_set, name_offset as u32)? {
+                resolved.push(name);
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn result_items(&self) -> impl Iterator<Item = bindings::fiftyoneDegreesResultHash> + '_ {
+        let results = unsafe { *self.results_ptr };
+        (0..results.count as usize).map(move |i| unsafe { *results.items.add(i) })
+    }
+
+    /// Returns the index of the component with the name, compared case-insensitively.
+    fn component_index(&self, component: &str) -> FiftyOneDegreesResult<Option<usize>> {
+        let data_set = self.data_set_ptr();
+        let components = unsafe { (*data_set).componentsList };
+        for i in 0..components.count as usize {
+            let component_ptr =
+                unsafe { (*components.items.add(i)).data.ptr } as *const bindings::fiftyoneDegreesComponent;
+            let name_offset = unsafe { (*component_ptr).nameOffset };
+            let name = data_set_string(data_set, name_offset as u32)?;
+            if name.is_some_and(|name| name.eq_ignore_ascii_case(component)) {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads every loaded boolean property, such as `IsMobile` or `HasTouchScreen`, in one pass.
+    ///
+    /// Properties without a value are skipped.
+    pub fn capabilities(&self) -> FiftyOneDegreesResult<BTreeMap<PropertyName, bool>> {
+        let mut capabilities = BTreeMap::new();
+        for known in KNOWN_PROPERTIES {
+            if !self.is_exposed(known.name) {
+                continue;
+            }
+            let is_boolean = self.property_metadata(known.property)?.is_some_and(|metadata| {
+                metadata.value_type
+                    == bindings::e_fiftyone_degrees_property_value_type_FIFTYONE_DEGREES_PROPERTY_VALUE_TYPE_BOOLEAN
+            });
+            if !is_boolean {
+                continue;
+            }
+            let value = self
+                .get_value_as_string(known.property)?
+                .and_then(|value| self.bool_parsing.parse(&value));
+            if let Some(value) = value {
+                capabilities.insert(known.property, value);
+            }
+        }
+        Ok(capabilities)
+    }
+
+    /// Returns the alternative model names sharing the detected hardware profile.
+    pub fn hardware_model_variants(&self) -> FiftyOneDegreesResult<Vec<String>> {
+        self.read_values(PropertyName::HardwareModelVariants)
+    }
+
+    /// Returns every hardware model variant paired with the hardware vendor.
+    ///
+    /// Empty if the vendor is unknown.
+    pub fn hardware_vendor_models(&self) -> FiftyOneDegreesResult<Vec<(String, String)>> {
+        let Some(vendor) = self.get_value_as_string(PropertyName::HardwareVendor)? else {
+            return Ok(Vec::new());
+        };
+        let models = self
+            .hardware_model_variants()?
+            .into_iter()
+            .map(|model| (vendor.clone(), model))
+            .collect();
+        Ok(models)
+    }
+
+    fn data_set_ptr(&self) -> *mut bindings::fiftyoneDegreesDataSetHash {
+        unsafe { (*self.results_ptr).b.b.dataSet as *mut bindings::fiftyoneDegreesDataSetHash }
+    }
+
+    /// Returns metadata of the property or `None` if the property is not loaded.
+    fn property_metadata(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Option<PropertyMetadata>> {
+        let mut item = CollectionItem::new();
+        let Some(property) = self.property_ptr(property_name, &mut item)? else {
+            return Ok(None);
+        };
+
+        let property = unsafe { *property };
+        Ok(Some(PropertyMetadata {
+            value_type: property.valueType as u32,
+            is_list: property.isList != 0,
+            component_index: property.componentIndex,
+        }))
+    }
+
+    /// Reads the property from the properties collection into `item`, `None` if the property
+    /// is not loaded. The pointer is valid while `item` is alive.
+    fn property_ptr(
+        &self,
+        property_name: PropertyName,
+        item: &mut CollectionItem,
+    ) -> FiftyOneDegreesResult<Option<*mut bindings::fiftyoneDegreesProperty>> {
+        let property_name_cstring = property_name.to_cstring()?;
+        let data_set = self.data_set_ptr();
+        let property_index = unsafe {
+            bindings::fiftyoneDegreesPropertiesGetPropertyIndexFromName(
+                (*data_set).b.b.available,
+                property_name_cstring.as_ptr(),
+            )
+        };
+        if property_index < 0 {
+            return Ok(None);
+        }
+
+        let mut exception = new_exception();
+        let exception = &raw mut exception;
+        let property = unsafe {
+            bindings::fiftyoneDegreesPropertyGet(
+                (*data_set).properties,
+                property_index as u32,
+                item.as_mut_ptr(),
+                exception,
+            )
+        };
+        verify_exception(exception, Operation::ReadProperty)?;
+        if property.is_null() {
+            return Err(UnsafeOperationError(format!(
+                "Failed to read metadata of property: {}: got null",
+                property_name
+            )));
+        }
+        Ok(Some(property))
+    }
+
+    /// Returns the candidate values of the property with the difference of the result they
+    /// come from, sorted by difference.
+    ///
+    /// Every result matching the component of the property contributes the values of its
+    /// profile, so evidence with several headers can yield conflicting candidates. A value
+    /// given by several results is kept with its lowest difference.
+    pub fn get_values_with_difference(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Vec<(String, i32)>> {
+        self.check_exposed(property_name)?;
+        let mut property_item = CollectionItem::new();
+        let Some(property) = self.property_ptr(property_name, &mut property_item)? else {
+            return Ok(Vec::new());
+        };
+        let component_index = unsafe { (*property).componentIndex } as usize;
+        let data_set = self.data_set_ptr();
+
+        let mut candidates: Vec<(String, i32)> = Vec::new();
+        for result in self.result_items() {
+            if result.profileOffsets.is_null() {
+                continue;
+            }
+            let profile_offset = unsafe { *result.profileOffsets.add(component_index) };
+            if profile_offset == NULL_PROFILE_OFFSET {
+                continue;
+            }
+
+            let mut profile_item = CollectionItem::new();
+            let profile = self.profile_ptr(profile_offset, &mut profile_item)?;
+
+            let mut name_offsets: Vec<i32> = Vec::new();
+            let mut exception = new_exception();
+            let exception = &raw mut exception;
+            unsafe {
+                bindings::fiftyoneDegreesProfileIterateValuesForProperty(
+                    (*data_set).values,
+                    profile,
+                    property,
+                    &mut name_offsets as *mut Vec<i32> as *mut _,
+                    Some(collect_value_name_offset),
+                    exception,
+                )
+            };
+            verify_exception(exception, Operation::ReadProperty)?;
+
+            for name_offset in name_offsets {
+                let Some(value) = data_set_string(data_set, name_offset as u32)?
+                    .filter(|value| self.is_present(value))
+                else {
+                    continue;
+                };
+                match candidates.iter_mut().find(|(candidate, _)| *candidate == value) {
+                    Some((_, difference)) => *difference = (*difference).min(result.difference),
+                    None => candidates.push((value, result.difference)),
+                }
+            }
+        }
+        candidates.sort_by_key(|&(_, difference)| difference);
+        Ok(candidates)
+    }
+
+    /// Returns the values of the property, each with the ID of the profile providing it.
+    ///
+    /// All values of a property come from the profile matched for the component the property
+    /// belongs to, taken from the first result matching the component.
+    pub fn value_sources(
+        &self,
+        property_name: PropertyName,
+    ) -> FiftyOneDegreesResult<Vec<(String, u32)>> {
+        let Some(metadata) = self.property_metadata(property_name)? else {
+            return Ok(Vec::new());
+        };
+        let component_index = metadata.component_index as usize;
+        let profile_offset = self
+            .result_items()
+            .filter(|result| !result.profileOffsets.is_null())
+            .map(|result| unsafe { *result.profileOffsets.add(component_index) })
+            .find(|&offset| offset != NULL_PROFILE_OFFSET);
+        let Some(profile_offset) = profile_offset else {
+            return Ok(Vec::new());
+        };
+
+        let profile_id = self.profile_id(profile_offset)?;
+        Ok(self
+            .read_values(property_name)?
+            .into_iter()
+            .map(|value| (value, profile_id))
+            .collect())
+    }
+
+    /// Reads the ID of the profile at the offset within the profiles collection.
+    fn profile_id(&self, profile_offset: u32) -> FiftyOneDegreesResult<u32> {
+        let mut item = CollectionItem::new();
+        let profile = self.profile_ptr(profile_offset, &mut item)?;
+        Ok(unsafe { (*profile).profileId })
+    }
+
+    /// Reads the profile at the offset into `item`. The pointer is valid while `item` is alive.
+    fn profile_ptr(
+        &self,
+        profile_offset: u32,
+        item: &mut CollectionItem,
+    ) -> FiftyOneDegreesResult<*mut bindings::fiftyoneDegreesProfile> {
+        let profiles = unsafe { (*self.data_set_ptr()).profiles };
+        let get = unsafe { (*profiles).get }.ok_or_else(|| {
+            UnsafeOperationError(String::from("Profiles collection has no get method"))
+        })?;
+
+        let mut exception = new_exception();
+        let exception = &raw mut exception;
+        let profile = unsafe { get(profiles, profile_offset, item.as_mut_ptr(), exception) }
+            as *mut bindings::fiftyoneDegreesProfile;
+        verify_exception(exception, Operation::ReadProperty)?;
+        if profile.is_null() {
+            return Err(UnsafeOperationError(format!(
+                "Failed to read profile at offset: {}: got null",
+                profile_offset
+            )));
+        }
+        Ok(profile)
+    }
+
+    /// Checks whether a value read from the data set counts as present.
+    fn is_present(&self, value: &str) -> bool {
+        if self.filter_unknown_values {
+            is_meaningful_value(value)
+        } else {
+            !value.is_empty()
+        }
+    }
+
+    /// Reads every value of the property individually, empty if the property has no values.
+    fn read_values(&self, property_name: PropertyName) -> FiftyOneDegreesResult<Vec<String>> {
+        self.check_exposed(property_name)?;
+        let Some(list_cache) = &self.list_cache else {
+            return self.collect_values(property_name);
+        };
+        if let Some(values) = list_cache.borrow().get(&property_name) {
+            return Ok(values.clone());
+        }
+        let values = self.collect_values(property_name)?;
+        list_cache.borrow_mut().insert(property_name, values.clone());
+        Ok(values)
+    }
+
+    /// Checks whether the values of the property are memoized.
+    #[cfg(test)]
+    pub(crate) fn is_memoized(&self, property_name: PropertyName) -> bool {
+        self.list_cache
+            .as_ref()
+            .is_some_and(|list_cache| list_cache.borrow().contains_key(&property_name))
+    }
+
+    fn collect_values(&self, property_name: PropertyName) -> FiftyOneDegreesResult<Vec<String>> {
+        let data_set = self.data_set_ptr();
+        let required_index = self.required_property_index(property_name)?;
+        if required_index < 0 || self.is_cleared() {
+            return Ok(Vec::new());
+        }
+
+        let mut exception = new_exception();
+        let exception = &raw mut exception;
+        let has_values = unsafe {
+            bindings::fiftyoneDegreesResultsHashGetHasValues(
+                self.results_ptr,
+                required_index,
+                exception,
+            )
+        };
+        verify_exception(exception, Operation::ReadProperty)?;
+        if !has_values {
+            return Ok(Vec::new());
+        }
+
+        let list = unsafe {
+            bindings::fiftyoneDegreesResultsHashGetValues(self.results_ptr, required_index, exception)
+        };
+        verify_exception(exception, Operation::ReadProperty)?;
+        if list.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let list = unsafe { *list };
+        let mut values = Vec::with_capacity(list.count as usize);
+        for i in 0..list.count as usize {
+            let value = unsafe { (*list.items.add(i)).data.ptr } as *mut bindings::fiftyoneDegreesValue;
+            let mut item = CollectionItem::new();
+            let name = unsafe {
+                bindings::fiftyoneDegreesValueGetName(
+                    (*data_set).strings,
+                    value,
+                    item.as_mut_ptr(),
+                    exception,
+                )
+            };
+            verify_exception(exception, Operation::ReadProperty)?;
+            if let Some(name) = read_fiftyone_string(name).filter(|s| self.is_present(s)) {
+                values.push(name);
+            }
+        }
+        Ok(values)
+    }
+
+    pub fn get_value(&self, property_name: &str) -> FiftyOneDegreesResult<Option<Cow<'_, str>>> {
+        if !self.is_exposed(property_name) {
+            return Err(PropertyNotExposed(property_name.to_string()));
+        }
+        let property_name_cstring = build_cstring(CStringKind::PropertyName, property_name)?;
+        let val_str = self.read_values_string(&property_name_cstring)?;
+
+        Ok(Some(val_str).filter(|s| !s.is_empty()).map(Cow::Owned))
+    }
+
+    /// Reads the values of the property joined by the separator, using the thread's buffer.
+    fn read_values_string(&self, property_name: &CStr) -> FiftyOneDegreesResult<String> {
+        self.read_values_str(property_name, |value| value.to_owned())
+    }
+
+    /// Passes the values of the property joined by the separator to `read`, without copying
+    /// them out of the thread's buffer.
+    fn read_values_str<T>(
+        &self,
+        property_name: &CStr,
+        read: impl FnOnce(&str) -> T,
+    ) -> FiftyOneDegreesResult<T> {
+        if self.is_cleared() {
+            return Ok(read(""));
+        }
+        with_value_buffer(self.value_buffer_size, |buf| {
+            read_growing(buf, |buf| {
+                let mut exception = new_exception();
+                let exception = &raw mut exception;
+                let required_len = unsafe {
+                    bindings::fiftyoneDegreesResultsHashGetValuesString(
+                        self.results_ptr,
+                        property_name.as_ptr(),
+                        buf.as_mut_ptr(),
+                        buf.len(),
+                        self.separator.as_ptr(),
+                        exception,
+                    )
+                };
+                verify_exception(exception, Operation::ReadProperty)?;
+                Ok(required_len)
+            })?;
+            let val_str = unsafe { CStr::from_ptr(buf.as_ptr()) };
+            Ok(read(&val_str.to_string_lossy()))
+        })
+    }
+
+    /// Passes the value of the property to `read` if present, as filtered by
+    /// [`ResultData::get_value_as_string`].
+    fn read_value_with<T>(
+        &self,
+        property_name: PropertyName,
+        read: &mut dyn FnMut(&str) -> T,
+    ) -> FiftyOneDegreesResult<Option<T>> {
+        self.check_exposed(property_name)?;
+        if let Some(value) =
+            self.read_lazily(property_name, |results| results.read_value_with(property_name, read))?
+        {
+            return Ok(value);
+        }
+        let property_name_cstring = property_name.to_cstring()?;
+        self.read_values_str(&property_name_cstring, |value| {
+            self.is_present(value).then(|| read(value))
+        })
+    }
+
+    /// Overwrites `out` with the value of the property, returns whether the value is present.
+    fn read_value_into(
+        &self,
+        property_name: PropertyName,
+        out: &mut String,
+    ) -> FiftyOneDegreesResult<bool> {
+        let read = self.read_value_with(property_name, &mut |value| {
+            out.clear();
+            out.push_str(value);
+        })?;
+        Ok(read.is_some())
+    }
+
+    /// Like [`ResultData::read_value_into`], reusing the string of `out` if it has one.
+    fn read_optional_value_into(
+        &self,
+        property_name: PropertyName,
+        out: &mut Option<String>,
+    ) -> FiftyOneDegreesResult<()> {
+        let mut value = out.take().unwrap_or_default();
+        if self.read_value_into(property_name, &mut value)? {
+            *out = Some(value);
+        }
+        Ok(())
+    }
+}
+
+/// Owned copy of selected detection values, detached from the FFI results.
+///
+/// Unlike [`ResultData`], a snapshot is `Send + Clone` and can be handed off to
+/// other threads or tasks freely.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionSnapshot {
+    values: Vec<(PropertyName, Option<String>)>,
+}
+
+impl DetectionSnapshot {
+    /// Returns the captured value or `None` if the property was absent or not captured.
+    pub fn get(&self, property_name: PropertyName) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(name, _)| *name == property_name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn values(&self) -> &[(PropertyName, Option<String>)] {
+        &self.values
+    }
+}
+
+/// Immutable copy of every value of a detection, created by [`ResultData::reader`].
+///
+/// The reader is `Send + Sync`, so one detection can be read from several threads at
+/// once, e.g. behind an `Arc`. List values are joined with the separator of the result.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultReader {
+    values: HashMap<String, String>,
+}
+
+impl ResultReader {
+    /// Returns the value or `None` if the property has no value or was not loaded.
+    pub fn get(&self, property_name: PropertyName) -> Option<&str> {
+        self.get_by_name(property_name.to_str())
+    }
+
+    /// Same as [`ResultReader::get`] for a property name given as a string, e.g. from
+    /// configuration.
+    pub fn get_by_name(&self, property_name: &str) -> Option<&str> {
+        self.values.get(property_name).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Client hint negotiation headers of a response, see [`Manager::client_hint_response`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientHintResponse {
+    /// Comma separated client hints used by the data set
+    pub accept_ch: String,
+    /// Lifetime of the hints in seconds
+    pub accept_ch_lifetime: u64,
+    /// The hints of `accept_ch` which browsers don't send by default, for which the request
+    /// should be retried
+    pub critical_ch: String,
+}
+
+impl ClientHintResponse {
+    /// Returns the `(header_name, value)` pairs to set on the response, omitting
+    /// `Critical-CH` if no hint is critical.
+    pub fn to_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![
+            (String::from("Accept-CH"), self.accept_ch.clone()),
+            (String::from("Accept-CH-Lifetime"), self.accept_ch_lifetime.to_string()),
+        ];
+        if !self.critical_ch.is_empty() {
+            headers.push((String::from("Critical-CH"), self.critical_ch.clone()));
+        }
+        headers
+    }
+}
+
+/// Browser identified by a detection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Browser {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Native device identifiers, as reported to native apps rather than in a User-Agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NativeDevice {
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub name: Option<String>,
+    pub platform: Option<String>,
+}
+
+/// Operating system identified by a detection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Platform {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Value of the `DeviceType` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceType {
+    Console,
+    Desktop,
+    EReader,
+    IoT,
+    Kiosk,
+    MediaHub,
+    Mobile,
+    SmallScreen,
+    SmartPhone,
+    SmartSpeaker,
+    SmartWatch,
+    Tablet,
+    Tv,
+    VehicleDisplay,
+    /// Device type added to the data set after this list was written
+    Other(String),
+}
+
+impl DeviceType {
+    fn from_value(value: &str) -> Self {
+        match value {
+            "Console" => DeviceType::Console,
+            "Desktop" => DeviceType::Desktop,
+            "EReader" => DeviceType::EReader,
+            "IoT" => DeviceType::IoT,
+            "Kiosk" => DeviceType::Kiosk,
+            "MediaHub" => DeviceType::MediaHub,
+            "Mobile" => DeviceType::Mobile,
+            "SmallScreen" => DeviceType::SmallScreen,
+            "SmartPhone" => DeviceType::SmartPhone,
+            "SmartSpeaker" => DeviceType::SmartSpeaker,
+            "SmartWatch" => DeviceType::SmartWatch,
+            "Tablet" => DeviceType::Tablet,
+            "Tv" => DeviceType::Tv,
+            "Vehicle Display" => DeviceType::VehicleDisplay,
+            other => DeviceType::Other(other.to_string()),
+        }
+    }
+}
+
+/// Coarse family of the `PlatformName` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlatformFamily {
+    IOS,
+    Android,
+    Windows,
+    MacOS,
+    Linux,
+    ChromeOS,
+    /// Name of any other platform, empty if unknown
+    Other(String),
+}
+
+impl PlatformFamily {
+    fn from_value(value: &str) -> Self {
+        match value {
+            "iOS" | "iPadOS" => PlatformFamily::IOS,
+            "Android" => PlatformFamily::Android,
+            "macOS" | "Mac OS" | "Mac OS X" => PlatformFamily::MacOS,
+            "Linux" | "Ubuntu" | "Debian" | "Fedora" => PlatformFamily::Linux,
+            "ChromeOS" | "Chrome OS" => PlatformFamily::ChromeOS,
+            windows if windows.starts_with("Windows") => PlatformFamily::Windows,
+            other => PlatformFamily::Other(other.to_string()),
+        }
+    }
+}
+
+/// Screen size in pixels, from `ScreenPixelsWidth` and `ScreenPixelsHeight`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Popularity ranks from `BrowserRank`, `PlatformRank` and `HardwareRank`, 1 for the most
+/// popular.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ranks {
+    pub browser: Option<u32>,
+    pub platform: Option<u32>,
+    pub hardware: Option<u32>,
+}
+
+/// Emulation state of a browser, derived from `IsEmulatingDesktop` and `IsEmulatingDevice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emulation {
+    None,
+    /// Mobile browser requesting the desktop version of sites
+    Desktop,
+    /// Desktop browser emulating a mobile device, e.g. in developer tools
+    Device,
+}
+
+/// Owned summary of a detection returned by [`Manager::detect_report`].
+///
+/// Fields are `None` if the value is unknown or the property isn't loaded by the manager.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceReport {
+    pub browser: Option<Browser>,
+    pub device_type: Option<DeviceType>,
+    pub is_mobile: Option<bool>,
+    pub platform: Option<Platform>,
+    pub match_metrics: MatchMetrics,
+}
+
+/// Problem with an evidence item found by [`Manager::validate_evidence`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvidenceIssue {
+    EmptyValue { key: String },
+    UnrecognizedKey { key: String },
+    ContainsNul { key: String },
+}
+
+/// Entry counts of the main collections in the loaded data set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatasetStats {
+    pub profiles: u32,
+    pub values: u32,
+    pub strings: u32,
+    pub nodes: u32,
+}
+
+/// Licensing strings embedded in the data file header, for attribution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseInfo {
+    /// Copyright notice of the data file
+    pub copyright: String,
+}
+
+/// Date in the data file header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DataFileDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl From<bindings::fiftyoneDegreesDate> for DataFileDate {
+    fn from(date: bindings::fiftyoneDegreesDate) -> Self {
+        DataFileDate {
+            year: { date.year } as u16,
+            month: date.month,
+            day: date.day,
+        }
+    }
+}
+
+/// Identification of the loaded data file, e.g. to alert when it goes stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFileInfo {
+    /// Date the data file was published
+    pub published: DataFileDate,
+    /// Date a newer data file is expected to be published
+    pub next_update: DataFileDate,
+    /// Product name of the data file, see [`Manager::product_name`]
+    pub tier: String,
+    /// Format version of the data file, `major.minor.build.revision`
+    pub version: String,
+}
+
+/// Item fetched from one of the data set collections, released back to the collection on drop.
+struct CollectionItem {
+    item: bindings::fiftyoneDegreesCollectionItem,
+}
+
+impl Drop for CollectionItem {
+    fn drop(&mut self) {
+        let collection = self.item.collection;
+        if !collection.is_null()
+            && let Some(release) = unsafe { (*collection).release }
+        {
+            unsafe { release(&mut self.item) };
+        }
+    }
+}
+
+impl CollectionItem {
+    fn new() -> Self {
+        let mut item = bindings::fiftyoneDegreesCollectionItem::default();
+        unsafe { bindings::fiftyoneDegreesDataReset(&mut item.data) };
+        Self { item }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut bindings::fiftyoneDegreesCollectionItem {
+        &mut self.item
+    }
+}
+
+/// Reads a string from the strings collection of the data set.
+fn data_set_string(
+    data_set: *mut bindings::fiftyoneDegreesDataSetHash,
+    offset: u32,
+) -> FiftyOneDegreesResult<Option<String>> {
+    let mut item = CollectionItem::new();
+    let mut exception = new_exception();
+    let exception = &raw mut exception;
+    let string = unsafe {
+        bindings::fiftyoneDegreesStringGet((*data_set).strings, offset, item.as_mut_ptr(), exception)
+    };
+    verify_exception(exception, Operation::ReadProperty)?;
+    Ok(read_fiftyone_string(string))
+}
+
+/// Profile values callback collecting the name offset of every value into a `Vec<i32>` state.
+unsafe extern "C" fn collect_value_name_offset(
+    state: *mut std::ffi::c_void,
+    item: *mut bindings::fiftyoneDegreesCollectionItem,
+) -> bool {
+    let value = unsafe { (*item).data.ptr } as *const bindings::fiftyoneDegreesValue;
+    let name_offsets = unsafe { &mut *(state as *mut Vec<i32>) };
+    name_offsets.push(unsafe { (*value).nameOffset });
+    true
+}
+
+/// Returns the modification time and size of the file, `None` if they can't be read.
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Parses `name="value", ...` members of a structured hint bundle, `None` if malformed.
+#[cfg(feature = "structured-hints")]
+fn parse_hint_dictionary(dictionary: &str) -> Option<Vec<(String, String)>> {
+    let mut members = Vec::new();
+    let mut chars = dictionary.trim().chars().peekable();
+    while chars.peek().is_some() {
+        let name = chars
+            .by_ref()
+            .take_while(|c| *c != '=')
+            .collect::<String>()
+            .trim()
+            .to_ascii_lowercase();
+        if name.is_empty() || chars.next() != Some('"') {
+            return None;
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => value.push(chars.next()?),
+                c => value.push(c),
+            }
+        }
+        members.push((name, value));
+
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        match chars.next() {
+            None => break,
+            Some(',') => {}
+            Some(_) => return None,
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    }
+    Some(members)
+}
+
+/// Reference to the active data set, released back to the manager on drop.
+struct DataSet {
+    data_set_ptr: *mut bindings::fiftyoneDegreesDataSetHash,
+}
+
+impl Drop for DataSet {
+    fn drop(&mut self) {
+        unsafe {
+            bindings::fiftyoneDegreesDataSetHashRelease(self.data_set_ptr);
+        }
+    }
+}
+
+impl DataSet {
+    fn new(manager_ptr: *mut bindings::fiftyoneDegreesResourceManager) -> FiftyOneDegreesResult<Self> {
+        let data_set_ptr = unsafe { bindings::fiftyoneDegreesDataSetHashGet(manager_ptr) };
+        if data_set_ptr.is_null() {
+            return Err(UnsafeOperationError(String::from(
+                "Failed to get data set: got null",
+            )));
+        }
+        Ok(Self { data_set_ptr })
+    }
+
+    fn header(&self) -> bindings::fiftyoneDegreesDataSetHashHeader {
+        unsafe { (*self.data_set_ptr).header }
+    }
+
+    /// Checks whether the property is loaded from the data file.
+    fn has_property(&self, property_name: PropertyName) -> FiftyOneDegreesResult<bool> {
+        let property_name_cstring = property_name.to_cstring()?;
+        let index = unsafe {
+            bindings::fiftyoneDegreesPropertiesGetRequiredPropertyIndexFromName(
+                (*self.data_set_ptr).b.b.available,
+                property_name_cstring.as_ptr(),
+            )
+        };
+        Ok(index >= 0)
+    }
+
+    /// Returns the names of the HTTP headers used by the data set.
+    fn headers(&self) -> Vec<String> {
+        let headers = unsafe { (*self.data_set_ptr).b.b.uniqueHeaders };
+        if headers.is_null() {
+            return Vec::new();
+        }
+        (0..unsafe { (*headers).count } as usize)
+            .map(|i| unsafe { *(*headers).items.add(i) })
+            .filter(|header| !header.name.is_null())
+            .map(|header| {
+                let name = unsafe {
+                    std::slice::from_raw_parts(header.name as *const u8, header.nameLength)
+                };
+                String::from_utf8_lossy(name).into_owned()
+            })
+            .collect()
+    }
+
+    /// Checks whether the HTTP header is used by the data set, ignoring case.
+    fn has_header(&self, name: &str) -> bool {
+        let Ok(name_cstring) = build_cstring(CStringKind::EvidenceKey, name) else {
+            return false;
+        };
+        let headers = unsafe { (*self.data_set_ptr).b.b.uniqueHeaders };
+        if headers.is_null() {
+            return false;
+        }
+        let index =
+            unsafe { bindings::fiftyoneDegreesHeaderGetIndex(headers, name_cstring.as_ptr(), name.len()) };
+        index >= 0
+    }
+}
+
+/// Locks the mutex, ignoring poisoning: the guarded state is valid after every update.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// State of a manager created with `lazy_properties`, shared with its results.
+struct LazyProperties {
+    path_cstring: CString,
+    hash_config: ConfigHash,
+    _temp_dir: Option<Arc<TempDir>>,
+    /// Comma separated list of the properties loaded so far, locked for the whole reload
+    property_names: Mutex<String>,
+    /// Manager with the additionally loaded properties, replaces the initial one once created
+    instance: Mutex<Option<ResourceManager>>,
+    /// Freed managers, kept allocated since results of previous detections may still refer to them
+    retired: Mutex<Vec<ResourceManager>>,
+}
+
+// The resource managers are thread-safe and the mutable state is behind the mutexes.
+unsafe impl Send for LazyProperties {}
+unsafe impl Sync for LazyProperties {}
+
+impl Drop for LazyProperties {
+    fn drop(&mut self) {
+        let instance = self.instance.get_mut();
+        if let Some(instance) = instance.unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            unsafe {
+                bindings::fiftyoneDegreesResourceManagerFree(instance.as_mut());
+            }
+        }
+    }
+}
+
+impl LazyProperties {
+    // The instance stays locked until the results or the data set hold their reference to
+    // its data set, so that a concurrent reload can't free it in between.
+
+    /// Creates results on the manager with the extended properties, `None` until one is loaded.
+    fn create_results(
+        &self,
+        overrides_capacity: u32,
+    ) -> Option<*mut bindings::fiftyoneDegreesResultsHash> {
+        lock(&self.instance)
+            .as_mut()
+            .map(|instance| create_results(instance.as_mut(), overrides_capacity))
+    }
+
+    /// Gets the data set of the manager with the extended properties, `None` until one is loaded.
+    fn data_set(&self) -> Option<FiftyOneDegreesResult<DataSet>> {
+        lock(&self.instance)
+            .as_mut()
+            .map(|instance| DataSet::new(instance.as_mut()))
+    }
+
+    /// Reloads the data file with the property added to the loaded ones.
+    fn load(&self, property_name: PropertyName) -> FiftyOneDegreesResult<()> {
+        let mut property_names = lock(&self.property_names);
+        if property_names.split(',').any(|name| name == property_name.to_str()) {
+            return Ok(());
+        }
+        let mut expanded = property_names.clone();
+        if !expanded.is_empty() {
+            expanded.push(',');
+        }
+        expanded.push_str(property_name.to_str());
+
+        let mut hash_config = self.hash_config;
+        let instance = Manager::init_instance(
+            DataSource::File(&self.path_cstring),
+            &mut hash_config,
+            Some(&expanded),
+        )?;
+        let previous = lock(&self.instance).replace(instance);
+        if let Some(mut previous) = previous {
+            unsafe {
+                bindings::fiftyoneDegreesResourceManagerFree(previous.as_mut());
+            }
+            lock(&self.retired).push(previous);
+        }
+        *property_names = expanded;
+        Ok(())
+    }
+}
+
+pub struct Manager {
+    instance: ResourceManager,
+    overrides_capacity: u32,
+    lazy: Option<Arc<LazyProperties>>,
+    /// `None` for a data set which wasn't loaded from a file, see [`Manager::from_fd`]
+    source_file: Mutex<Option<SourceFile>>,
+    /// Comma separated list of the properties passed to the C library, `*` for all
+    requested_properties: String,
+    property_names: Option<Arc<[PropertyName]>>,
+    _temp_dir: Option<Arc<TempDir>>,
+    hint_policy: HintPolicy,
+    bool_parsing: BoolParsing,
+    filter_unknown_values: bool,
+    memoize_lists: bool,
+    lenient_evidence: bool,
+    value_buffer_size: usize,
+    /// Copy of the data file made by [`Manager::new_from_copy`], deleted on drop
+    copied_file: Option<PathBuf>,
+    /// Data the instance was loaded from in memory, i.e. the contents read by
+    /// [`Manager::from_fd`] or the mapping of [`Manager::from_mmap`], freed after the instance
+    _memory: Option<Box<dyn AsRef<[u8]>>>,
+    /// Status of the fatal error of a previous detection, see [`Manager::is_poisoned`]
+    poisoned: OnceLock<StatusCode>,
+    evidence_cache: Mutex<EvidenceCache>,
+    client_hint_lifetime: Duration,
+    progressive_max_difference: i32,
+    /// `ManagerConfig::require_tier`, checked again by [`Manager::reload_from_file`]
+    require_tier: Option<String>,
+    /// `ManagerConfig::strict_properties`, checked again by [`Manager::reload_from_file`]
+    strict_properties: bool,
+}
+
+// The resource manager of the C library is thread-safe: detections only share the read-only
+// data set, every detection creates its own evidence and results. The state shared on the
+// Rust side is immutable after loading, atomic or behind a mutex.
+unsafe impl Send for Manager {}
+unsafe impl Sync for Manager {}
+
+/// Data file a manager was loaded from, replaced by [`Manager::reload_from_file`].
+struct SourceFile {
+    /// Canonical path
+    path: PathBuf,
+    /// Modification time and size of the data file when it was loaded
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            stamp: file_stamp(&path),
+            path,
+        }
+    }
+}
+
+/// Normalized evidence set: header names lowercased, items sorted.
+type EvidenceKey = Vec<(String, String)>;
+
+/// Results of [`Manager::detect_cached_evidence`], evicted least recently used first.
+///
+/// Every use of a key is stamped with a new generation, so a lookup only pushes the key
+/// instead of searching it in `order`. Amortized O(1) per lookup and insertion.
+struct EvidenceCache {
+    capacity: usize,
+    /// Result of each key and the generation of its last use
+    entries: HashMap<EvidenceKey, (Arc<ResultReader>, u64)>,
+    /// Uses of the keys from the least to the most recent. A use is stale if its key was used
+    /// again later, stale uses are skipped by the eviction and dropped by `compact`.
+    order: VecDeque<(u64, EvidenceKey)>,
+    generation: u64,
+}
+
+impl EvidenceCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            generation: 0,
+        }
+    }
+
+    fn get(&mut self, key: &EvidenceKey) -> Option<Arc<ResultReader>> {
+        let (reader, used) = self.entries.get_mut(key)?;
+        self.generation += 1;
+        *used = self.generation;
+        let reader = reader.clone();
+        self.order.push_back((self.generation, key.clone()));
+        self.compact();
+        Some(reader)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn insert(&mut self, key: EvidenceKey, reader: Arc<ResultReader>) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let Some((generation, evicted)) = self.order.pop_front() else {
+                break;
+            };
+            if self.is_last_use(generation, &evicted) {
+                self.entries.remove(&evicted);
+            }
+        }
+        self.generation += 1;
+        self.order.push_back((self.generation, key.clone()));
+        self.entries.insert(key, (reader, self.generation));
+        self.compact();
+    }
+
+    fn is_last_use(&self, generation: u64, key: &EvidenceKey) -> bool {
+        self.entries.get(key).is_some_and(|(_, used)| *used == generation)
+    }
+
+    /// Drops the stale uses once they outnumber the entries, which keeps `order` within twice
+    /// the capacity.
+    fn compact(&mut self) {
+        if self.order.len() > 2 * self.capacity {
+            let mut order = std::mem::take(&mut self.order);
+            order.retain(|(generation, key)| self.is_last_use(*generation, key));
+            self.order = order;
+        }
+    }
+}
+
+/// Where a resource manager loads the data set from.
+enum DataSource<'a> {
+    File(&'a CStr),
+    /// Must outlive the resource manager. Only read, as the C library doesn't write to data
+    /// it isn't asked to free (`freeData`).
+    Memory(&'a [u8]),
+}
+
+impl Drop for Manager {
     fn drop(&mut self) {
         unsafe {
             bindings::fiftyoneDegreesResourceManagerFree(self.instance.as_mut());
         }
+        if let Some(copied_file) = &self.copied_file {
+            let _ = std::fs::remove_file(copied_file);
+        }
+    }
+}
+
+impl Manager {
+    fn build_config(
+        manager_config: &ManagerConfig,
+        temp_dir: Option<&TempDir>,
+    ) -> FiftyOneDegreesResult<ConfigHash> {
+        /*
+        config.b.b.usesUpperPrefixedHeaders = false;
+        config.b.updateMatchedUserAgent = false;
+        */
+
+        let profile = manager_config.performance_profile.unwrap_or(match temp_dir {
+            Some(_) => PerformanceProfile::BalancedTemp,
+            None => PerformanceProfile::HighPerformance,
+        });
+        let mut config = profile.config();
+        if let Some(temp_dir) = temp_dir {
+            config.b.b.tempDirs = temp_dir.dirs.as_ptr() as *mut _;
+            config.b.b.tempDirCount = temp_dir.dirs.len() as _;
+        }
+        if let Some(concurrency) = manager_config.concurrency {
+            config.strings.concurrency = concurrency;
+            config.components.concurrency = concurrency;
+            config.maps.concurrency = concurrency;
+            config.properties.concurrency = concurrency;
+            config.values.concurrency = concurrency;
+            config.profiles.concurrency = concurrency;
+            config.rootNodes.concurrency = concurrency;
+            config.nodes.concurrency = concurrency;
+            config.profileOffsets.concurrency = concurrency;
+        }
+        Ok(config)
+    }
+
+    pub(crate) fn init_status_to_error(status: u32) -> FiftyOneDegreesError {
+        let error = match status {
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INSUFFICIENT_HANDLES => {
+                "Not enough handles for the configured concurrency, try lowering ManagerConfig::concurrency"
+            }
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_COPY_ERROR => {
+                "Failed to copy the data file, check that the temp directory exists and is writable"
+            }
+            _ => "Status check failed",
+        };
+        InternalApiError(
+            Operation::InitManager,
+            StatusCode::from_raw(status),
+            status_to_error_message(status),
+            error,
+        )
+    }
+
+    pub fn new(config: ManagerConfig) -> FiftyOneDegreesResult<Self> {
+        Self::load(&config, &config.data_file_path)
+    }
+
+    /// Creates a manager from a copy of the data file made in `temp_dir`, leaving the original
+    /// file free to be replaced while the manager is alive. The copy is deleted on drop.
+    ///
+    /// [`Manager::source_file`] returns the path of the copy.
+    pub fn new_from_copy(
+        data_file_path: &Path,
+        temp_dir: &Path,
+        property_names: Option<&[PropertyName]>,
+    ) -> FiftyOneDegreesResult<Self> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COPY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        verify_data_file_path(data_file_path)?;
+        let file_name = data_file_path
+            .file_name()
+            .ok_or(IOError("Data file path has no file name", None))?;
+        let copy_path = temp_dir.join(format!(
+            "{}-{}-{}",
+            std::process::id(),
+            COPY_COUNT.fetch_add(1, Ordering::Relaxed),
+            file_name.to_string_lossy()
+        ));
+        std::fs::copy(data_file_path, &copy_path).map_err(|e| {
+            IOError(
+                "Failed to copy the data file, check that the temp directory exists and is writable",
+                Some(e),
+            )
+        })?;
+
+        let config = ManagerConfig {
+            property_names: property_names.map(<[_]>::to_vec),
+            ..ManagerConfig::new(data_file_path)
+        };
+        match Self::load(&config, &copy_path) {
+            Ok(mut manager) => {
+                manager.copied_file = Some(copy_path);
+                Ok(manager)
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&copy_path);
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn load(config: &ManagerConfig, data_file_path: &Path) -> FiftyOneDegreesResult<Self> {
+        verify_data_file_path(data_file_path)?;
+
+        let source_file = data_file_path
+            .canonicalize()
+            .map_err(|e| IOError("Failed to canonicalize data file path", Some(e)))?;
+        let path_cstring = source_file
+            .to_str()
+            .ok_or(IOError("Failed to convert data file path to string", None))
+            .and_then(|s| build_cstring(CStringKind::FilePath, s))?;
+
+        let property_names = Self::property_list(config);
+
+        let temp_dir = config.temp_dir.as_deref().map(TempDir::new).transpose()?.map(Arc::new);
+        let mut hash_config = Self::build_config(config, temp_dir.as_deref())?;
+        let instance = Self::init_instance(
+            DataSource::File(&path_cstring),
+            &mut hash_config,
+            property_names.as_deref(),
+        )?;
+
+        let lazy = (config.lazy_properties && property_names.is_some()).then(|| {
+            Arc::new(LazyProperties {
+                path_cstring,
+                hash_config,
+                _temp_dir: temp_dir.clone(),
+                property_names: Mutex::new(property_names.unwrap_or_default()),
+                instance: Mutex::new(None),
+                retired: Mutex::new(Vec::new()),
+            })
+        });
+
+        Self::from_instance(instance, config, Some(source_file), lazy, temp_dir, None)
+    }
+
+    /// Creates the manager around a loaded instance and applies the checks of `config`.
+    ///
+    /// `memory` owns the data the instance was loaded from if it was loaded from memory.
+    fn from_instance(
+        instance: ResourceManager,
+        config: &ManagerConfig,
+        source_file: Option<PathBuf>,
+        lazy: Option<Arc<LazyProperties>>,
+        temp_dir: Option<Arc<TempDir>>,
+        memory: Option<Box<dyn AsRef<[u8]>>>,
+    ) -> FiftyOneDegreesResult<Self> {
+        let manager = Self {
+            instance,
+            overrides_capacity: 0,
+            lazy,
+            source_file: Mutex::new(source_file.map(SourceFile::new)),
+            requested_properties: Self::property_list(config).unwrap_or_else(|| String::from("*")),
+            property_names: config.property_names.as_deref().map(Arc::from),
+            _temp_dir: temp_dir,
+            hint_policy: config.hint_policy,
+            bool_parsing: config.bool_parsing,
+            filter_unknown_values: config.filter_unknown_values,
+            memoize_lists: config.memoize_lists,
+            lenient_evidence: config.lenient_evidence,
+            value_buffer_size: config.value_buffer_size,
+            copied_file: None,
+            _memory: memory,
+            poisoned: OnceLock::new(),
+            evidence_cache: Mutex::new(EvidenceCache::new(config.evidence_cache_size)),
+            client_hint_lifetime: config.client_hint_lifetime,
+            progressive_max_difference: config.progressive_max_difference,
+            require_tier: config.require_tier.clone(),
+            strict_properties: config.strict_properties,
+        };
+        manager.check_loaded(config)
     }
-}
 
-impl Manager {
-    fn build_config() -> FiftyOneDegreesResult<ConfigHash> {
-        //let mut config = Box::new(unsafe { bindings::fiftyoneDegreesHashHighPerformanceConfig });
-        /*
-        config.nodes.concurrency = 4;
-        config.profiles.concurrency = 4;
-        config.profileOffsets.concurrency = 4;
-        config.rootNodes.concurrency = 4;
-        config.values.concurrency = 4;
-        config.strings.concurrency = 4;
-        config.b.b.usesUpperPrefixedHeaders = false;
-        config.b.updateMatchedUserAgent = false;
-        */
+    /// Returns the comma separated list of the properties to load, all if `None`.
+    fn property_list(config: &ManagerConfig) -> Option<String> {
+        config
+            .property_names
+            .as_ref()
+            .map(|names| names.iter().map(PropertyName::to_str).join(","))
+    }
 
-        let config = unsafe { bindings::fiftyoneDegreesHashHighPerformanceConfig };
-        Ok(config)
+    /// Applies the checks and settings of `config` which need the loaded data set.
+    fn check_loaded(mut self, config: &ManagerConfig) -> FiftyOneDegreesResult<Self> {
+        if let Some(required) = &config.require_tier {
+            let actual = self.product_name()?;
+            if !actual.eq_ignore_ascii_case(required) {
+                return Err(TierMismatch {
+                    required: required.clone(),
+                    actual,
+                });
+            }
+        }
+        if config.strict_properties
+            && let Some(property_names) = &config.property_names
+        {
+            let data_set = self.data_set()?;
+            let mut missing = Vec::new();
+            for name in property_names.iter() {
+                if !data_set.has_property(*name)? {
+                    missing.push(name.to_str().to_string());
+                }
+            }
+            if !missing.is_empty() {
+                return Err(RequiredPropertyMissing(missing));
+            }
+        }
+        if config.allow_overrides {
+            let overridable = unsafe { (*self.data_set()?.data_set_ptr).b.b.overridable };
+            if !overridable.is_null() {
+                self.overrides_capacity = unsafe { (*overridable).count };
+            }
+        }
+        Ok(self)
     }
 
-    pub fn new(config: ManagerConfig) -> FiftyOneDegreesResult<Self> {
-        verify_data_file_path(config.data_file_path)?;
+    /// Creates a manager from a data file opened by the caller, e.g. passed by a sandbox which
+    /// forbids opening it by path. The contents are read into memory, the descriptor is left
+    /// open and can be closed once this returns.
+    ///
+    /// `data_file_path`, `lazy_properties` and `temp_dir` of the config are ignored. The
+    /// manager has no [`Manager::source_file`], [`Manager::file_changed`] and
+    /// [`Manager::reload_from_file`] fail.
+    #[cfg(unix)]
+    pub fn from_fd(fd: RawFd, config: ManagerConfig) -> FiftyOneDegreesResult<Self> {
+        // Borrowed only, the descriptor belongs to the caller
+        let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+        let mut memory = Vec::new();
+        file.read_to_end(&mut memory)
+            .map_err(|e| IOError("Failed to read the data file descriptor", Some(e)))?;
+        let memory = memory.into_boxed_slice();
+
+        let mut hash_config = Self::build_config(&config, None)?;
+        // The memory is owned by the manager, not the C library
+        hash_config.b.b.freeData = false;
+        let instance = Self::init_instance(
+            DataSource::Memory(&memory),
+            &mut hash_config,
+            Self::property_list(&config).as_deref(),
+        )?;
+
+        Self::from_instance(instance, &config, None, None, None, Some(Box::new(memory)))
+    }
 
-        let path_cstring = config
+    /// Creates a manager from a read-only memory mapping of the data file instead of reading
+    /// it into memory. The kernel is advised of random access, so it doesn't read ahead of
+    /// the pages touched by detections, which are scattered over the whole file.
+    ///
+    /// Pages are loaded on first access, which makes the first detections slower than with
+    /// [`Manager::new`] on a cold page cache. The data file must be replaced by moving a new
+    /// file over it rather than written in place while the manager is alive.
+    /// `lazy_properties` and `temp_dir` are not supported and ignored.
+    #[cfg(all(unix, feature = "mmap"))]
+    pub fn from_mmap(config: ManagerConfig) -> FiftyOneDegreesResult<Self> {
+        verify_data_file_path(&config.data_file_path)?;
+        let source_file = config
             .data_file_path
             .canonicalize()
-            .map_err(|e| IOError("Failed to canonicalize data file path", Some(e)))?
-            .to_str()
-            .ok_or_else(|| IOError("Failed to convert data file path to string", None))
-            .and_then(|s| build_cstring(CStringKind::FilePath, s))?;
+            .map_err(|e| IOError("Failed to canonicalize data file path", Some(e)))?;
+        let file = File::open(&source_file)
+            .map_err(|e| IOError("Failed to open the data file", Some(e)))?;
+        let mapped = unsafe { memmap2::MmapOptions::new().map(&file) }
+            .map_err(|e| IOError("Failed to map the data file", Some(e)))?;
+        mapped
+            .advise(memmap2::Advice::Random)
+            .map_err(|e| IOError("Failed to advise random access to the data file", Some(e)))?;
 
-        let properties_cstring: CString; // This variable must survive until Manager is created (compiler doesn't check its lifecycle)
-        let properties: *mut bindings::fiftyone_degrees_properties_required_t;
-        if config.property_names.is_some() {
-            let property_names = config
-                .property_names
-                .unwrap()
-                .iter()
-                .map(PropertyName::to_str)
-                .join(",");
+        let mut hash_config = Self::build_config(&config, None)?;
+        // The mapping is owned by the manager, not the C library
+        hash_config.b.b.freeData = false;
+        let instance = Self::init_instance(
+            DataSource::Memory(&mapped),
+            &mut hash_config,
+            Self::property_list(&config).as_deref(),
+        )?;
 
-            properties_cstring = build_cstring(CStringKind::PropertyName, &property_names)?;
+        Self::from_instance(instance, &config, Some(source_file), None, None, Some(Box::new(mapped)))
+    }
 
-            let mut p = Properties {
-                existing: null_mut(),
-                array: null_mut(),
-                string: properties_cstring.as_ptr(),
-                count: 0,
-            };
-            properties = &mut p;
-        } else {
-            // All properties if not specified
-            properties = null_mut();
+    /// Creates a manager from the primary data file, or from the fallback one if the primary
+    /// is missing or corrupt.
+    ///
+    /// Useful to prefer an enterprise data file when present while bundling a free one.
+    /// [`Manager::source_file`] tells which of the files was loaded.
+    pub fn new_with_fallback(
+        primary: &Path,
+        fallback: &Path,
+        property_names: Option<&[PropertyName]>,
+    ) -> FiftyOneDegreesResult<Self> {
+        let primary_config = ManagerConfig {
+            property_names: property_names.map(<[_]>::to_vec),
+            ..ManagerConfig::new(primary)
+        };
+        match Self::new(primary_config) {
+            Err(AssertionError(Operation::ReadDataFile, _))
+            | Err(InternalApiError(
+                _,
+                StatusCode::FileNotFound | StatusCode::CorruptData,
+                _,
+                _,
+            )) => Self::new(ManagerConfig {
+                property_names: property_names.map(<[_]>::to_vec),
+                ..ManagerConfig::new(fallback)
+            }),
+            result => result,
         }
+    }
 
-        let mut config = Self::build_config()?;
-        //let mut manager = std::mem::MaybeUninit::<bindings::fiftyoneDegreesResourceManager>::uninit();
-        let mut manager =
-            Box::new(unsafe { std::mem::zeroed::<bindings::fiftyoneDegreesResourceManager>() });
-        let exception = null_mut();
+    /// Returns the canonical path of the loaded data file, empty for a manager created by
+    /// [`Manager::from_fd`].
+    pub fn source_file(&self) -> PathBuf {
+        lock(&self.source_file)
+            .as_ref()
+            .map(|source_file| source_file.path.clone())
+            .unwrap_or_default()
+    }
+
+    /// Checks whether evidence overrides apply to the detections of this manager.
+    ///
+    /// `false` if the manager wasn't created with `allow_overrides`, or if none of the loaded
+    /// properties can be overridden.
+    pub fn overrides_enabled(&self) -> bool {
+        self.overrides_capacity > 0
+    }
+
+    /// Returns the comma separated list of the properties the data set was loaded with, as
+    /// passed to the C library, or `*` if all properties are loaded. Includes the properties
+    /// loaded since by `lazy_properties`.
+    pub fn requested_properties_string(&self) -> String {
+        match &self.lazy {
+            Some(lazy) => lock(&lazy.property_names).clone(),
+            None => self.requested_properties.clone(),
+        }
+    }
+
+    /// Checks whether the data file was modified or replaced since it was loaded, based on its
+    /// modification time and size. A data file which can no longer be read counts as changed.
+    ///
+    /// Fails for a manager created by [`Manager::from_fd`], which has no data file.
+    pub fn file_changed(&self) -> FiftyOneDegreesResult<bool> {
+        let source_file = lock(&self.source_file);
+        let source_file = source_file.as_ref().ok_or(NO_SOURCE_FILE)?;
+        let current = file_stamp(&source_file.path);
+        Ok(current.is_none() || current != source_file.stamp)
+    }
+
+    /// Replaces the data set with one loaded from the data file, with the same properties and
+    /// config. Detections running during the reload finish on the previous data set, which
+    /// stays alive until their results are dropped.
+    ///
+    /// If the new file can't be loaded, e.g. it is corrupt or of another format version, the
+    /// previous data set is kept. So it is if the file fails the `require_tier` or
+    /// `strict_properties` checks of the config, for which it is loaded once more with the
+    /// low memory profile before the reload. Cached results of
+    /// [`Manager::detect_cached_evidence`] are dropped. A poisoned manager stays poisoned,
+    /// see [`Manager::is_poisoned`]. Managers with `lazy_properties` can't be reloaded.
+    pub fn reload_from_file(&self, path: impl AsRef<Path>) -> FiftyOneDegreesResult<()> {
+        if self.lazy.is_some() {
+            return Err(AssertionError(
+                Operation::ReadDataFile,
+                "Managers with lazy properties can't be reloaded, create a new manager",
+            ));
+        }
+        if lock(&self.source_file).is_none() {
+            return Err(NO_SOURCE_FILE);
+        }
+        let path = path.as_ref();
+        verify_data_file_path(path)?;
+        let source_file = path
+            .canonicalize()
+            .map_err(|e| IOError("Failed to canonicalize data file path", Some(e)))?;
+        let path_cstring = source_file
+            .to_str()
+            .ok_or(IOError("Failed to convert data file path to string", None))
+            .and_then(|s| build_cstring(CStringKind::FilePath, s))?;
+        if self.require_tier.is_some() || self.strict_properties {
+            let checked = ManagerConfig {
+                property_names: self.property_names.as_deref().map(<[_]>::to_vec),
+                performance_profile: Some(PerformanceProfile::LowMemory),
+                require_tier: self.require_tier.clone(),
+                strict_properties: self.strict_properties,
+                ..ManagerConfig::new(&source_file)
+            };
+            drop(Self::new(checked)?);
+        }
 
+        let mut exception = new_exception();
+        let exception = &raw mut exception;
         let status = unsafe {
-            bindings::fiftyoneDegreesHashInitManagerFromFile(
-                manager.as_mut(),
-                &mut config,
-                properties,
+            bindings::fiftyoneDegreesHashReloadManagerFromFile(
+                self.instance_ptr(),
                 path_cstring.as_ptr(),
                 exception,
             )
         };
+        verify_exception(exception, Operation::ReadDataFile)?;
+        if status != bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_SUCCESS {
+            return Err(Self::init_status_to_error(status));
+        }
+
+        *lock(&self.source_file) = Some(SourceFile::new(source_file));
+        lock(&self.evidence_cache).clear();
+        Ok(())
+    }
+
+    /// Replaces the tier required by [`Manager::reload_from_file`].
+    #[cfg(test)]
+    pub(crate) fn set_require_tier(&mut self, tier: &str) {
+        self.require_tier = Some(tier.to_string());
+    }
+
+    /// Initializes a resource manager from the data file.
+    ///
+    /// `property_names` is a comma separated list of the properties to load, all if `None`.
+    fn init_instance(
+        source: DataSource,
+        hash_config: &mut ConfigHash,
+        property_names: Option<&str>,
+    ) -> FiftyOneDegreesResult<ResourceManager> {
+        // These must survive until the manager is initialized (compiler doesn't check their lifecycle)
+        let properties_cstring = property_names
+            .map(|names| build_cstring(CStringKind::PropertyName, names))
+            .transpose()?;
+        let mut properties = properties_cstring.as_ref().map(|names| Properties {
+            existing: null_mut(),
+            array: null_mut(),
+            string: names.as_ptr(),
+            count: 0,
+        });
+        // All properties if not specified
+        let properties_ptr = properties
+            .as_mut()
+            .map_or(null_mut(), |p| p as *mut Properties);
+
+        //let mut manager = std::mem::MaybeUninit::<bindings::fiftyoneDegreesResourceManager>::uninit();
+        let mut manager =
+            Box::new(unsafe { std::mem::zeroed::<bindings::fiftyoneDegreesResourceManager>() });
+        let mut exception = new_exception();
+        let exception = &raw mut exception;
+
+        let status = match source {
+            DataSource::File(path_cstring) => unsafe {
+                bindings::fiftyoneDegreesHashInitManagerFromFile(
+                    manager.as_mut(),
+                    hash_config,
+                    properties_ptr,
+                    path_cstring.as_ptr(),
+                    exception,
+                )
+            },
+            DataSource::Memory(memory) => unsafe {
+                bindings::fiftyoneDegreesHashInitManagerFromMemory(
+                    manager.as_mut(),
+                    hash_config,
+                    properties_ptr,
+                    memory.as_ptr().cast_mut().cast(),
+                    memory.len() as _,
+                    exception,
+                )
+            },
+        };
 
         verify_exception(exception, Operation::InitManager)?;
 
+        if status == bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_TEMP_FILE_ERROR
+            && hash_config.b.b.tempDirCount > 0
+        {
+            let dir = unsafe { CStr::from_ptr(*hash_config.b.b.tempDirs) };
+            return Err(TempFileError {
+                dir: PathBuf::from(dir.to_string_lossy().into_owned()),
+            });
+        }
+        if status == bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_REQ_PROP_NOT_PRESENT {
+            // Only reported if none of the properties is in the data file
+            if let Some(property_names) = property_names {
+                return Err(RequiredPropertyMissing(
+                    property_names.split(',').map(String::from).collect(),
+                ));
+            }
+        }
         if status != bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_SUCCESS {
-            return Err(InternalApiError(
-                Operation::InitManager,
-                status,
-                status_to_error_message(status),
-                "Status check failed",
-            ));
+            return Err(Self::init_status_to_error(status));
         }
 
-        Ok(Self { instance: manager })
+        Ok(manager)
     }
 
     /// Detects device properties based on the provided evidence.
@@ -435,14 +3329,18 @@ impl Manager {
     /// - `Ok(ResultData)` containing device detection results.
     /// - `Err(FiftyOneDegreesError)` if detection fails.
     ///
-    /// # Safety and Threading
-    /// ⚠️ **Not thread-safe.**
+    /// # Threading
+    /// The manager is `Send + Sync` and detections can run concurrently on a shared
+    /// manager, e.g. behind an `Arc`. Every detection creates its own evidence and results,
+    /// only the read-only data set is shared. A file-backed data set (`temp_dir`) needs
+    /// `ManagerConfig::concurrency` to be at least the number of simultaneous detections.
     ///
-    /// This method uses internal mutable state via FFI and must not be called concurrently
-    /// from multiple threads or asynchronous tasks unless external synchronization is used.
+    /// The returned [`ResultData`] is not `Send`, it must be read on the detecting thread;
+    /// see [`ResultData::reader`] to share its values.
     ///
-    /// If thread-safe behavior is needed, consider using a `Mutex<Manager>` or other
-    /// synchronization primitives to guard access to this function.
+    /// With the `metrics` feature, every successful detection increments the
+    /// `fiftyone.detect.count` counter and records its duration in seconds and its match
+    /// difference to the `fiftyone.detect.duration` and `fiftyone.detect.difference` histograms.
     ///
     /// # Example
     /// ```
@@ -453,6 +3351,62 @@ impl Manager {
         &self,
         evidence_data: &[(EvidenceName, &str)],
     ) -> FiftyOneDegreesResult<ResultData> {
+        self.detect_values(evidence_data)
+    }
+
+    /// Same as `detect`, but takes owned evidence values, e.g. collected at runtime.
+    pub fn detect_owned(
+        &self,
+        evidence_data: &[(EvidenceName, String)],
+    ) -> FiftyOneDegreesResult<ResultData> {
+        self.detect_values(evidence_data)
+    }
+
+    fn detect_values<V: AsRef<str>>(
+        &self,
+        evidence_data: &[(EvidenceName, V)],
+    ) -> FiftyOneDegreesResult<ResultData> {
+        let (evidence, warnings) = self.build_evidence(evidence_data)?;
+        let mut result = self.detect_evidence(&evidence)?;
+        result.warnings = warnings;
+        Ok(result)
+    }
+
+    /// Same as `detect`, also returning the evidence submitted to the C library as key/value
+    /// pairs, e.g. to log the input of a detection with its output. Items skipped with
+    /// `lenient_evidence` are not included.
+    pub fn detect_audited(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<(ResultData, Vec<(String, String)>)> {
+        let (evidence, warnings) = self.build_evidence(evidence_data)?;
+        let mut result = self.detect_evidence(&evidence)?;
+        result.warnings = warnings;
+        let echo = evidence
+            .iter()
+            .map(|(key, val)| (key.to_string(), val.to_string()))
+            .collect();
+        Ok((result, echo))
+    }
+
+    /// Builds the evidence array, returning the errors of the items skipped with
+    /// `lenient_evidence` alongside.
+    fn build_evidence<V: AsRef<str>>(
+        &self,
+        evidence_data: &[(EvidenceName, V)],
+    ) -> FiftyOneDegreesResult<(Evidence, Vec<FiftyOneDegreesError>)> {
+        let headers: Vec<(&str, &str)> = evidence_data
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_ref()))
+            .collect();
+        self.build_header_evidence(&headers)
+    }
+
+    /// Same as [`Manager::build_evidence`] for headers with arbitrary names.
+    fn build_header_evidence(
+        &self,
+        evidence_data: &[(&str, &str)],
+    ) -> FiftyOneDegreesResult<(Evidence, Vec<FiftyOneDegreesError>)> {
         if evidence_data.len() == 0 {
             return Err(AssertionError(
                 Operation::CreateEvidence,
@@ -461,13 +3415,705 @@ impl Manager {
         }
 
         let mut evidence = Evidence::new(evidence_data.len() as u32)?;
+        let mut warnings = Vec::new();
+
+        for (key, val) in evidence_data {
+            match evidence.add(key, val) {
+                Ok(()) => {}
+                Err(e) if self.lenient_evidence => warnings.push(e),
+                Err(e) => return Err(e),
+            }
+        }
+        if warnings.len() == evidence_data.len() {
+            // At least one item must be usable
+            return Err(warnings.swap_remove(0));
+        }
+        Ok((evidence, warnings))
+    }
+
+    /// Checks that the evidence can be detected from, without creating results.
+    ///
+    /// Fails like [`Manager::detect`] would if the evidence is empty, can't be passed to the
+    /// C library, or contains no header used by the data set.
+    pub fn can_detect(&self, evidence_data: &[(EvidenceName, &str)]) -> FiftyOneDegreesResult<()> {
+        let (evidence, _) = self.build_evidence(evidence_data)?;
+        let filtered = self.hint_policy.apply(&evidence)?;
+        let evidence = filtered.as_ref().unwrap_or(&evidence);
+        let data_set = self.data_set()?;
+        if !evidence.iter().any(|(key, _)| data_set.has_header(key)) {
+            return Err(AssertionError(
+                Operation::ApplyEvidence,
+                "Evidence produced no results, it must contain at least one relevant header",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Detects device properties from HTTP headers with arbitrary names, e.g. all headers of
+    /// a request. Headers not used by the data set are ignored by the detection.
+    pub fn detect_raw(&self, headers: &[(&str, &str)]) -> FiftyOneDegreesResult<ResultData> {
+        let (evidence, warnings) = self.build_header_evidence(headers)?;
+        let mut result = self.detect_evidence(&evidence)?;
+        result.warnings = warnings;
+        Ok(result)
+    }
+
+    /// Detects device properties from a JSON object of HTTP headers.
+    ///
+    /// Every top-level string field is used as header evidence, other fields are skipped.
+    #[cfg(feature = "serde")]
+    pub fn detect_json(&self, evidence_json: &serde_json::Value) -> FiftyOneDegreesResult<ResultData> {
+        let fields = evidence_json
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, val)| val.as_str().map(|val| (key.as_str(), val)))
+            .collect::<Vec<_>>();
+
+        self.detect_raw(&fields)
+    }
+
+    /// Detects device properties from the headers of an `http` request, e.g. in an Axum or
+    /// Actix handler.
+    ///
+    /// Headers used by the data set, such as `user-agent` and the `sec-ch-*` client hints, are
+    /// passed as evidence. Other headers and values which are not valid UTF-8 are skipped, and
+    /// if none is left the detection fails like [`Manager::detect`] without evidence.
+    #[cfg(feature = "http")]
+    pub fn detect_from_headers(
+        &self,
+        headers: &http::HeaderMap,
+    ) -> FiftyOneDegreesResult<ResultData> {
+        let fields = {
+            let data_set = self.data_set()?;
+            headers
+                .iter()
+                .filter(|(name, _)| data_set.has_header(name.as_str()))
+                .filter_map(|(name, val)| val.to_str().ok().map(|val| (name.as_str(), val)))
+                .collect::<Vec<_>>()
+        };
+
+        self.detect_raw(&fields)
+    }
+
+    /// Detects device properties from a base64 encoded bundle of client hints.
+    ///
+    /// The decoded bundle is a dictionary of `name="value"` members separated by commas,
+    /// where the name is a client hint header with or without the `sec-ch-` prefix and the
+    /// value is the header value as a quoted string (`\"` and `\\` escaped), e.g.
+    /// `ua="\"Chromium\";v=\"110\"", sec-ch-ua-platform="\"iOS\""`.
+    #[cfg(feature = "structured-hints")]
+    pub fn detect_structured_hints(&self, encoded: &str) -> FiftyOneDegreesResult<ResultData> {
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| {
+                AssertionError(
+                    Operation::CreateEvidence,
+                    "Structured hints are not valid base64",
+                )
+            })?;
+        let hints = std::str::from_utf8(&decoded)
+            .ok()
+            .and_then(parse_hint_dictionary)
+            .ok_or(AssertionError(
+                Operation::CreateEvidence,
+                "Structured hints are not a valid hint dictionary",
+            ))?;
+
+        let keys: Vec<Cow<str>> = hints
+            .iter()
+            .map(|(name, _)| match name.starts_with("sec-ch-") {
+                true => Cow::Borrowed(name.as_str()),
+                false => Cow::Owned(format!("sec-ch-{}", name)),
+            })
+            .collect();
+        let fields: Vec<(&str, &str)> = keys
+            .iter()
+            .zip(&hints)
+            .map(|(key, (_, val))| (key.as_ref(), val.as_str()))
+            .collect();
+
+        self.detect_raw(&fields)
+    }
+
+    /// Detects device properties and collects the commonly used ones into a [`DeviceReport`].
+    ///
+    /// The manager should load `BrowserName`, `BrowserVersion`, `DeviceType`, `IsMobile`,
+    /// `PlatformName` and `PlatformVersion` for a complete report.
+    pub fn detect_report(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<DeviceReport> {
+        let mut report = DeviceReport::default();
+        self.detect_into_report(evidence_data, &mut report)?;
+        Ok(report)
+    }
+
+    /// Same as [`Manager::detect_report`], but overwrites a report owned by the caller.
+    ///
+    /// The strings of the report are reused, so a report kept across detections avoids
+    /// allocating for every one of them.
+    pub fn detect_into_report(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+        report: &mut DeviceReport,
+    ) -> FiftyOneDegreesResult<()> {
+        let res = self.detect(evidence_data)?;
+
+        let mut browser = report.browser.take().unwrap_or_default();
+        if res.read_value_into(PropertyName::BrowserName, &mut browser.name)? {
+            res.read_optional_value_into(PropertyName::BrowserVersion, &mut browser.version)?;
+            report.browser = Some(browser);
+        }
+        let mut platform = report.platform.take().unwrap_or_default();
+        if res.read_value_into(PropertyName::PlatformName, &mut platform.name)? {
+            res.read_optional_value_into(PropertyName::PlatformVersion, &mut platform.version)?;
+            report.platform = Some(platform);
+        }
+        report.device_type =
+            res.read_value_with(PropertyName::DeviceType, &mut DeviceType::from_value)?;
+        report.is_mobile = res
+            .read_value_with(PropertyName::IsMobile, &mut |value| res.bool_parsing.parse(value))?
+            .flatten();
+        report.match_metrics = res.match_metrics();
+        Ok(())
+    }
+
+    /// Detects with all the properties of the manager, but only exposes `property_names` on
+    /// the result. Reading any other property fails with `PropertyNotExposed`, and the
+    /// reads over every property, such as [`ResultData::for_each_value`], skip them.
+    pub fn detect_with_properties(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+        property_names: &[PropertyName],
+    ) -> FiftyOneDegreesResult<ResultData> {
+        let mut result = self.detect(evidence_data)?;
+        result.exposed = Some(property_names.to_vec());
+        Ok(result)
+    }
+
+    /// Detects from the User-Agent and the `Sec-CH-UA` brands, given as parsed
+    /// `(brand, version)` pairs, e.g. by a web framework. The pairs are serialized back into
+    /// a `Sec-CH-UA` value.
+    pub fn detect_brands(
+        &self,
+        ua: &str,
+        brands: &[(&str, &str)],
+    ) -> FiftyOneDegreesResult<ResultData> {
+        let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+        let sec_ch_ua = brands
+            .iter()
+            .map(|(brand, version)| format!("{};v={}", quote(brand), quote(version)))
+            .join(", ");
+        if sec_ch_ua.is_empty() {
+            return self.detect(&[EvidenceName::UserAgent.value(ua)]);
+        }
+        self.detect(&[
+            EvidenceName::UserAgent.value(ua),
+            EvidenceName::SecChUa.value(&sec_ch_ua),
+        ])
+    }
+
+    /// Detects like [`Manager::detect`] and caches the values of the result, keyed by the
+    /// whole evidence set, so repeated evidence skips the detection. Header names are
+    /// compared case-insensitively and the order of the items doesn't matter, which suits
+    /// client hints where several headers make up the key.
+    ///
+    /// The cache holds up to `ManagerConfig::evidence_cache_size` evidence sets. Properties
+    /// loaded lazily after the detection are not in the cached values.
+    pub fn detect_cached_evidence(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<Arc<ResultReader>> {
+        let mut key: EvidenceKey = evidence_data
+            .iter()
+            .map(|(name, value)| (name.header_key().into_owned(), value.to_string()))
+            .collect();
+        key.sort();
+        if let Some(reader) = lock(&self.evidence_cache).get(&key) {
+            return Ok(reader);
+        }
+
+        let reader = Arc::new(self.detect(evidence_data)?.reader()?);
+        lock(&self.evidence_cache).insert(key, reader.clone());
+        Ok(reader)
+    }
+
+    /// Detects from the client hints only and repeats the detection with the User-Agent added
+    /// if the hints don't produce a close enough match, i.e. nothing is matched or the
+    /// difference is above `ManagerConfig::progressive_max_difference`. Saves matching the
+    /// User-Agent when the hints are enough.
+    pub fn detect_progressive(
+        &self,
+        ua: &str,
+        hints: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<(ResultData, DetectionPath)> {
+        if !hints.is_empty() {
+            let res = self.detect(hints)?;
+            if res.method()? != MatchMethod::None
+                && res.match_metrics().difference <= self.progressive_max_difference
+            {
+                return Ok((res, DetectionPath::Hints));
+            }
+        }
 
+        let mut evidence_data = Vec::with_capacity(hints.len() + 1);
+        evidence_data.push(EvidenceName::UserAgent.value(ua));
+        evidence_data.extend(hints.iter().cloned());
+        Ok((self.detect(&evidence_data)?, DetectionPath::UserAgentFallback))
+    }
+
+    /// Detects every User-Agent read from `reader`, one per line, and counts the detections
+    /// per `DeviceType`, e.g. to summarize an access log. Blank lines are skipped and devices
+    /// without a type are counted as `Unknown`.
+    pub fn histogram_device_types<R: BufRead>(
+        &self,
+        reader: R,
+    ) -> FiftyOneDegreesResult<HashMap<String, u64>> {
+        let mut histogram = HashMap::new();
+        for line in reader.lines() {
+            let line = line.map_err(|e| IOError("Failed to read a User-Agent line", Some(e)))?;
+            let ua = line.trim();
+            if ua.is_empty() {
+                continue;
+            }
+            let res = self.detect(&[EvidenceName::UserAgent.value(ua)])?;
+            let device_type = res
+                .get_value_as_string(PropertyName::DeviceType)?
+                .unwrap_or_else(|| String::from("Unknown"));
+            *histogram.entry(device_type).or_insert(0) += 1;
+        }
+        Ok(histogram)
+    }
+
+    /// Runs the match and returns only its metrics, e.g. for traffic quality monitoring.
+    ///
+    /// No property is read, so the manager can be created with a minimal property set.
+    pub fn detect_metrics_only(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<MatchMetrics> {
+        Ok(self.detect(evidence_data)?.match_metrics())
+    }
+
+    /// Checks whether the User-Agent belongs to an AI crawler, see [`ResultData::is_ai_agent`].
+    ///
+    /// Unknown values count as `false`. The manager must load `IsArtificialIntelligence`.
+    pub fn detect_ai_crawler(&self, ua: &str) -> FiftyOneDegreesResult<bool> {
+        let res = self.detect(&[EvidenceName::UserAgent.value(ua)])?;
+        Ok(res.is_ai_agent()?.unwrap_or(false))
+    }
+
+    /// Checks whether the User-Agents resolve to the same device, i.e. the same profile of
+    /// every component as identified by the `DeviceId`. See [`ResultData::fingerprint`].
+    pub fn same_device(&self, ua_a: &str, ua_b: &str) -> FiftyOneDegreesResult<bool> {
+        let a = self.detect(&[EvidenceName::UserAgent.value(ua_a)])?;
+        let b = self.detect(&[EvidenceName::UserAgent.value(ua_b)])?;
+        Ok(a.fingerprint()? == b.fingerprint()?)
+    }
+
+    /// Classifies every evidence item by whether the match, as identified by the
+    /// [`ResultData::fingerprint`], changes when the detection is repeated without it.
+    ///
+    /// This is a diagnostic and expensive: the evidence is detected once in full and once
+    /// more per item. Removing the only item counts as decisive if anything was matched.
+    pub fn evidence_contribution(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<Vec<(String, Contribution)>> {
+        let full = self.detect(evidence_data)?;
+        let fingerprint = full.fingerprint()?;
+        let data_set = self.data_set()?;
+
+        let mut contributions = Vec::with_capacity(evidence_data.len());
+        for (i, (key, _)) in evidence_data.iter().enumerate() {
+            let others: Vec<_> = evidence_data
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, item)| item.clone())
+                .collect();
+            let decisive = if others.is_empty() {
+                full.method()? != MatchMethod::None
+            } else {
+                self.detect(&others)?.fingerprint()? != fingerprint
+            };
+            let key = key.header_key();
+            let contribution = if decisive {
+                Contribution::Decisive
+            } else if data_set.has_header(&key) {
+                Contribution::Corroborating
+            } else {
+                Contribution::Ignored
+            };
+            contributions.push((key.into_owned(), contribution));
+        }
+        Ok(contributions)
+    }
+
+    /// Checks whether the data set detects from the HTTP header, ignoring case. Keys which
+    /// can't be headers, e.g. containing a NUL, are not used.
+    pub fn uses_evidence_key(&self, key: &str) -> FiftyOneDegreesResult<bool> {
+        Ok(self.data_set()?.has_header(key))
+    }
+
+    /// Checks the evidence without running a detection and reports the unusable items.
+    ///
+    /// An item can have an empty value, a NUL in its key or value, or a key which is not
+    /// a header used by the data set. The key check is skipped if the data set can't be read.
+    pub fn validate_evidence(&self, evidence_data: &[(EvidenceName, &str)]) -> Vec<EvidenceIssue> {
+        let data_set = self.data_set().ok();
+        let mut issues = Vec::new();
         for (key, val) in evidence_data {
-            evidence.add(key.as_str(), val)?;
+            let key = key.header_key();
+            let key = key.as_ref();
+            if key.contains('\0') || val.contains('\0') {
+                issues.push(EvidenceIssue::ContainsNul {
+                    key: key.to_string(),
+                });
+            } else if val.is_empty() {
+                issues.push(EvidenceIssue::EmptyValue {
+                    key: key.to_string(),
+                });
+            } else if data_set.as_ref().is_some_and(|data_set| !data_set.has_header(key)) {
+                issues.push(EvidenceIssue::UnrecognizedKey {
+                    key: key.to_string(),
+                });
+            }
+        }
+        issues
+    }
+
+    /// Detects device properties from prebuilt [`Evidence`].
+    ///
+    /// Useful when evidence has to be assembled manually, e.g. with [`Evidence::add_bytes`].
+    /// The same threading restrictions as for [`Manager::detect`] apply.
+    pub fn detect_evidence(&self, evidence: &Evidence) -> FiftyOneDegreesResult<ResultData> {
+        if evidence.evidence_data.is_empty() {
+            return Err(AssertionError(
+                Operation::CreateEvidence,
+                "Evidence data must contain at least one item",
+            ));
+        }
+
+        if let Some(&status) = self.poisoned.get() {
+            return Err(ManagerPoisoned(status));
+        }
+
+        let filtered = self.hint_policy.apply(evidence)?;
+        let evidence = filtered.as_ref().unwrap_or(evidence);
+
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+
+        let results_ptr = self
+            .lazy
+            .as_ref()
+            .and_then(|lazy| lazy.create_results(self.overrides_capacity))
+            .unwrap_or_else(|| create_results(self.instance_ptr(), self.overrides_capacity));
+        let mut result = ResultData::new(results_ptr, evidence.evidence_ptr)
+            .inspect_err(|e| self.record_failure(e))?;
+        result.bool_parsing = self.bool_parsing;
+        result.filter_unknown_values = self.filter_unknown_values;
+        result.requested = self.property_names.clone();
+        result.value_buffer_size = self.value_buffer_size;
+        if self.memoize_lists {
+            result.list_cache = Some(RefCell::new(HashMap::new()));
+        }
+        if let Some(lazy) = &self.lazy {
+            result.lazy = Some(LazyReload {
+                lazy: lazy.clone(),
+                evidence_data: evidence.evidence_data.clone(),
+                overrides_capacity: self.overrides_capacity,
+                reloaded: RefCell::new(None),
+            });
         }
 
-        let manager_ptr = self.instance.as_ref() as *const _ as *mut _;
-        let result = ResultData::new(manager_ptr, evidence.evidence_ptr)?;
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("fiftyone.detect.count").increment(1);
+            metrics::histogram!("fiftyone.detect.duration")
+                .record(started.elapsed().as_secs_f64());
+            metrics::histogram!("fiftyone.detect.difference")
+                .record(result.match_metrics().difference as f64);
+        }
         Ok(result)
     }
+
+    /// Checks whether a previous detection failed with a fatal [`StatusCode`], after which
+    /// every detection fails with `ManagerPoisoned` instead of calling the C library again.
+    /// Reloading the data file doesn't clear it, a new manager is needed.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.get().is_some()
+    }
+
+    /// Poisons the manager if the error comes from a fatal status of the C library.
+    pub(crate) fn record_failure(&self, error: &FiftyOneDegreesError) {
+        if let InternalApiError(_, status, _, _) = error
+            && status.is_fatal()
+        {
+            let _ = self.poisoned.set(*status);
+        }
+    }
+
+    /// Returns the number of profiles, values, strings and nodes in the loaded data set.
+    pub fn dataset_stats(&self) -> FiftyOneDegreesResult<DatasetStats> {
+        let header = self.data_set()?.header();
+        Ok(DatasetStats {
+            profiles: header.profiles.count,
+            values: header.values.count,
+            strings: header.strings.count,
+            nodes: header.nodes.count,
+        })
+    }
+
+    /// Returns the size in bytes of the data set collections, as declared by their headers.
+    ///
+    /// With the default in-memory profile the collections are all held in memory, so this
+    /// is close to the resident size of the data set, excluding the small per-detection
+    /// allocations.
+    pub fn resident_bytes(&self) -> FiftyOneDegreesResult<usize> {
+        let header = self.data_set()?.header();
+        let collections = [
+            header.strings,
+            header.components,
+            header.maps,
+            header.properties,
+            header.values,
+            header.profiles,
+            header.rootNodes,
+            header.nodes,
+            header.profileOffsets,
+        ];
+        Ok(collections
+            .iter()
+            .map(|collection| collection.length as usize)
+            .sum())
+    }
+
+    /// Returns the names of the properties loaded from the data file: the requested ones it
+    /// contains, or all of its properties without `property_names`. Requested properties
+    /// missing from the file can fail the load instead with `strict_properties`.
+    pub fn available_properties(&self) -> FiftyOneDegreesResult<Vec<String>> {
+        let data_set = self.data_set()?;
+        let available = unsafe { (*data_set.data_set_ptr).b.b.available };
+        Ok((0..unsafe { (*available).count } as i32)
+            .filter_map(|required_index| {
+                read_fiftyone_string(unsafe {
+                    bindings::fiftyoneDegreesPropertiesGetNameFromRequiredIndex(
+                        available,
+                        required_index,
+                    )
+                })
+            })
+            .collect())
+    }
+
+    /// Checks whether the property is loaded from the data file, see
+    /// [`Manager::available_properties`]. `false` if the data set can't be read.
+    pub fn is_property_available(&self, property_name: PropertyName) -> bool {
+        self.data_set()
+            .and_then(|data_set| data_set.has_property(property_name))
+            .unwrap_or(false)
+    }
+
+    /// Returns the names of the loaded properties grouped by their category in the data set,
+    /// e.g. `Device`, `Browser` or `Hardware`.
+    pub fn properties_by_category(&self) -> FiftyOneDegreesResult<BTreeMap<String, Vec<String>>> {
+        let data_set = self.data_set()?;
+        let data_set_ptr = data_set.data_set_ptr;
+        let available = unsafe { (*data_set_ptr).b.b.available };
+        let mut categories = BTreeMap::<String, Vec<String>>::new();
+        for required_index in 0..unsafe { (*available).count } {
+            let property_index =
+                unsafe { (*(*available).items.add(required_index as usize)).propertyIndex };
+            let name = unsafe {
+                bindings::fiftyoneDegreesPropertiesGetNameFromRequiredIndex(
+                    available,
+                    required_index as i32,
+                )
+            };
+            let Some(name) = read_fiftyone_string(name) else {
+                continue;
+            };
+
+            let mut item = CollectionItem::new();
+            let mut exception = new_exception();
+            let exception = &raw mut exception;
+            let property = unsafe {
+                bindings::fiftyoneDegreesPropertyGet(
+                    (*data_set_ptr).properties,
+                    property_index,
+                    item.as_mut_ptr(),
+                    exception,
+                )
+            };
+            verify_exception(exception, Operation::ReadProperty)?;
+            if property.is_null() {
+                return Err(UnsafeOperationError(format!(
+                    "Failed to read metadata of property: {}: got null",
+                    name
+                )));
+            }
+            let category_offset = unsafe { (*property).categoryOffset };
+            let category = data_set_string(data_set_ptr, category_offset)?.unwrap_or_default();
+            categories.entry(category).or_default().push(name);
+        }
+        Ok(categories)
+    }
+
+    /// Returns the product name of the data file, e.g. `Lite`, `Enterprise` or `TAC`.
+    pub fn product_name(&self) -> FiftyOneDegreesResult<String> {
+        let data_set = self.data_set()?;
+        let name_offset = data_set.header().nameOffset;
+        data_set_string(data_set.data_set_ptr, name_offset as u32)?
+            .filter(|name| !name.is_empty())
+            .ok_or(AssertionError(
+                Operation::ReadDataFile,
+                "Data file header has no product name",
+            ))
+    }
+
+    /// Returns the publish dates, tier and format version of the loaded data file.
+    pub fn data_file_info(&self) -> FiftyOneDegreesResult<DataFileInfo> {
+        let tier = self.product_name()?;
+        let header = self.data_set()?.header();
+        Ok(DataFileInfo {
+            published: header.published.into(),
+            next_update: header.nextUpdate.into(),
+            tier,
+            version: format!(
+                "{}.{}.{}.{}",
+                { header.versionMajor },
+                { header.versionMinor },
+                { header.versionBuild },
+                { header.versionRevision }
+            ),
+        })
+    }
+
+    /// Returns the date the loaded data file was published.
+    pub fn data_published_date(&self) -> FiftyOneDegreesResult<DataFileDate> {
+        Ok(self.data_set()?.header().published.into())
+    }
+
+    /// Returns the date a newer data file is expected to be published.
+    pub fn data_next_update_date(&self) -> FiftyOneDegreesResult<DataFileDate> {
+        Ok(self.data_set()?.header().nextUpdate.into())
+    }
+
+    /// Same as [`Manager::product_name`].
+    pub fn data_tier(&self) -> FiftyOneDegreesResult<String> {
+        self.product_name()
+    }
+
+    /// Returns the format version of the loaded data file, see [`DataFileInfo::version`].
+    pub fn data_file_version(&self) -> FiftyOneDegreesResult<String> {
+        Ok(self.data_file_info()?.version)
+    }
+
+    /// Returns a short tag identifying the content of the data file, e.g. to key caches of
+    /// detection results: `<product>-<published date>-<format version>-<file tag>`, where the
+    /// file tag is the start of the unique id the data file was published with.
+    ///
+    /// The tag is the same for every load of the same data file and changes with a new one.
+    pub fn content_tag(&self) -> FiftyOneDegreesResult<String> {
+        let product = self.product_name()?;
+        let header = self.data_set()?.header();
+        let published = header.published;
+        let (major, minor) = (header.versionMajor, header.versionMinor);
+        let file_tag: String = header.tag[..4]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(format!(
+            "{}-{:04}-{:02}-{:02}-{}.{}-{}",
+            product,
+            { published.year },
+            published.month,
+            published.day,
+            major,
+            minor,
+            file_tag
+        ))
+    }
+
+    /// Returns the headers requesting the client hints used by the data set, e.g. to set on
+    /// the responses of a server negotiating client hints. The lifetime is
+    /// `ManagerConfig::client_hint_lifetime`.
+    pub fn client_hint_response(&self) -> FiftyOneDegreesResult<ClientHintResponse> {
+        let hints: Vec<String> = self
+            .data_set()?
+            .headers()
+            .into_iter()
+            .filter(|name| is_client_hint(name.as_bytes()))
+            .collect();
+        let critical_ch = hints
+            .iter()
+            .filter(|name| {
+                !DEFAULT_CLIENT_HINTS
+                    .iter()
+                    .any(|default| name.eq_ignore_ascii_case(default))
+            })
+            .join(", ");
+        Ok(ClientHintResponse {
+            accept_ch: hints.join(", "),
+            accept_ch_lifetime: self.client_hint_lifetime.as_secs(),
+            critical_ch,
+        })
+    }
+
+    /// Returns the URL of the 51Degrees distributor which serves updates of the loaded data
+    /// file for the license key, e.g. for an automated updater. The URL asks for the same
+    /// product and format version as the loaded file.
+    pub fn update_url(&self, license_key: &str) -> FiftyOneDegreesResult<String> {
+        let product = self.product_name()?;
+        let header = self.data_set()?.header();
+        let (major, minor) = (header.versionMajor, header.versionMinor);
+        let encode = |value: &str| {
+            value
+                .bytes()
+                .map(|byte| match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                        (byte as char).to_string()
+                    }
+                    _ => format!("%{:02X}", byte),
+                })
+                .collect::<String>()
+        };
+        Ok(format!(
+            "{}?LicenseKeys={}&Type=HashV{}{}&Download=True&Product=V{}{}",
+            DISTRIBUTOR_URL,
+            encode(license_key),
+            major,
+            minor,
+            major,
+            encode(&product)
+        ))
+    }
+
+    /// Returns the licensing strings of the data file. The Hash format only embeds the
+    /// copyright notice.
+    pub fn license_info(&self) -> FiftyOneDegreesResult<LicenseInfo> {
+        let data_set = self.data_set()?;
+        let copyright_offset = data_set.header().copyrightOffset;
+        let copyright = data_set_string(data_set.data_set_ptr, copyright_offset as u32)?
+            .filter(|copyright| !copyright.is_empty())
+            .ok_or(AssertionError(
+                Operation::ReadDataFile,
+                "Data file header has no copyright notice",
+            ))?;
+        Ok(LicenseInfo { copyright })
+    }
+
+    fn instance_ptr(&self) -> *mut bindings::fiftyoneDegreesResourceManager {
+        self.instance.as_ref() as *const _ as *mut _
+    }
+
+    fn data_set(&self) -> FiftyOneDegreesResult<DataSet> {
+        match self.lazy.as_ref().and_then(|lazy| lazy.data_set()) {
+            Some(data_set) => data_set,
+            None => DataSet::new(self.instance_ptr()),
+        }
+    }
 }
@@ -0,0 +1,67 @@
+//! Loading a data file downloaded from a URL, for quick starts without a data file at hand.
+
+use super::device_detection::{Manager, ManagerConfig, PropertyName};
+use super::utils::FiftyOneDegreesError::{self, IOError};
+use super::utils::FiftyOneDegreesResult;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Age after which a cached data file is downloaded again. Data files are updated weekly.
+const MAX_CACHE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+impl Manager {
+    /// Creates a manager from the data file at `url`, cached at `cache_path`.
+    ///
+    /// The file is downloaded if the cache is missing, empty or older than a week, and reused
+    /// otherwise. The download is written next to `cache_path` first, so a failed download
+    /// leaves the previous cache intact.
+    pub fn from_url(
+        url: &str,
+        cache_path: &Path,
+        property_names: Option<&[PropertyName]>,
+    ) -> FiftyOneDegreesResult<Self> {
+        if is_stale(cache_path) {
+            download(url, cache_path)?;
+        }
+        let config = ManagerConfig {
+            property_names: property_names.map(<[_]>::to_vec),
+            ..ManagerConfig::new(Path::new(""))
+        };
+        Self::load(&config, cache_path)
+    }
+}
+
+/// Checks whether the cached data file is missing, empty or too old to be used.
+fn is_stale(cache_path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(cache_path) else {
+        return true;
+    };
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    metadata.len() == 0 || age.is_none_or(|age| age > MAX_CACHE_AGE)
+}
+
+fn download(url: &str, cache_path: &Path) -> FiftyOneDegreesResult<()> {
+    let download_error = |cause: String| FiftyOneDegreesError::DownloadError {
+        url: url.to_string(),
+        cause,
+    };
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| download_error(e.to_string()))?;
+
+    let mut part_path = cache_path.as_os_str().to_owned();
+    part_path.push(".part");
+    let mut part_file = std::fs::File::create(&part_path)
+        .map_err(|e| IOError("Failed to create the data file download", Some(e)))?;
+    let copied = std::io::copy(&mut response.into_reader(), &mut part_file);
+    drop(part_file);
+    if let Err(e) = copied {
+        let _ = std::fs::remove_file(&part_path);
+        return Err(download_error(e.to_string()));
+    }
+    std::fs::rename(&part_path, cache_path)
+        .map_err(|e| IOError("Failed to move the data file download to the cache", Some(e)))
+}
@@ -9,7 +9,12 @@ mod bindings {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
+pub mod compat;
 pub mod device_detection;
+#[cfg(feature = "download")]
+pub mod download;
+#[cfg(feature = "arc-swap")]
+pub mod reloadable;
 pub mod utils;
 
 #[cfg(test)]
@@ -0,0 +1,43 @@
+//! Manager which can be replaced by a new one while other threads keep detecting.
+
+use super::device_detection::{EvidenceName, Manager, ManagerConfig, ResultData};
+use super::utils::FiftyOneDegreesResult;
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+
+/// Thread-safe manager whose data set can be replaced without interrupting detections.
+///
+/// Detections in progress during a [`ReloadableManager::reload`] finish on the previous
+/// manager. Results refer to the manager they were created on, which is freed once the last
+/// of them is dropped, so they stay readable after a reload.
+pub struct ReloadableManager {
+    current: ArcSwap<Manager>,
+}
+
+impl ReloadableManager {
+    /// Creates the initial manager.
+    pub fn new(config: ManagerConfig) -> FiftyOneDegreesResult<Self> {
+        Ok(Self {
+            current: ArcSwap::from_pointee(Manager::new(config)?),
+        })
+    }
+
+    /// Detects device properties with the current manager, see [`Manager::detect`].
+    pub fn detect(
+        &self,
+        evidence_data: &[(EvidenceName, &str)],
+    ) -> FiftyOneDegreesResult<ResultData> {
+        let manager = self.current.load_full();
+        let mut result = manager.detect(evidence_data)?;
+        result.manager = Some(manager);
+        Ok(result)
+    }
+
+    /// Creates a manager from the config and swaps it in for the following detections.
+    ///
+    /// The current manager is kept if the new one fails to load.
+    pub fn reload(&self, config: ManagerConfig) -> FiftyOneDegreesResult<()> {
+        self.current.store(Arc::new(Manager::new(config)?));
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use super::super::compat::property_for_capability;
+use super::super::device_detection::{EvidenceName, Manager, ManagerConfig, PropertyName};
+use super::super::utils::FiftyOneDegreesError;
+
+#[test]
+fn test_capability() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        property_for_capability("brand_name")?,
+        PropertyName::HardwareVendor
+    );
+
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::HardwareVendor])
+        .build();
+    let manager = Manager::new(config)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 15_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.2 Mobile/15E148 Safari/604.1",
+    )])?;
+    assert_eq!(res.capability("brand_name")?.as_deref(), Some("Apple"));
+
+    assert!(matches!(
+        property_for_capability("browser_name"),
+        Err(FiftyOneDegreesError::UnknownCapability {
+            nearest: PropertyName::BrowserName,
+            ..
+        })
+    ));
+
+    Ok(())
+}
@@ -1,5 +1,11 @@
+use super::super::bindings;
 use super::super::device_detection::{
-    EvidenceName, Manager, ManagerConfig, PropertyName,
+    BoolParsing, Contribution, DetectionPath, DeviceReport, DeviceType, Emulation, Evidence,
+    EvidenceIssue, EvidenceName, HintPolicy, Manager, ManagerConfig, MatchMethod,
+    PerformanceProfile, PlatformFamily, PropertyName, Resolution, TypedValue, value_buffer_resizes,
+};
+use super::super::utils::{
+    new_exception, verify_exception, ErrorKind, FiftyOneDegreesError, Operation, StatusCode,
 };
 
 #[test]
@@ -9,14 +15,14 @@ fn test_device_detect() -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("Data path: {:?}", data_file_path);
 
     let conf = ManagerConfig {
-        data_file_path,
-        property_names: Some(&[
+        property_names: Some(vec![
             PropertyName::BrowserName,
             PropertyName::DeviceType,
             PropertyName::PlatformName,
             PropertyName::PlatformVersion,
             PropertyName::IsMobile,
         ]),
+        ..ManagerConfig::new(data_file_path)
     };
 
     let manager = Manager::new(conf)?;
@@ -45,3 +51,2004 @@ fn test_device_detect() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+const IPHONE_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 15_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.2 Mobile/15E148 Safari/604.1";
+
+fn test_manager() -> Result<Manager, Box<dyn std::error::Error>> {
+    let conf = ManagerConfig {
+        property_names: Some(vec![
+            PropertyName::BrowserName,
+            PropertyName::DeviceType,
+            PropertyName::PlatformName,
+            PropertyName::PlatformVersion,
+            PropertyName::IsMobile,
+        ]),
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    };
+    Ok(Manager::new(conf)?)
+}
+
+#[test]
+fn test_requested_properties_string() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    assert_eq!(
+        manager.requested_properties_string(),
+        "BrowserName,DeviceType,PlatformName,PlatformVersion,IsMobile"
+    );
+
+    let manager = Manager::new(ManagerConfig::new(std::path::Path::new("data.hash")))?;
+    assert_eq!(manager.requested_properties_string(), "*");
+
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_send_to_thread() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let snapshot = res.snapshot(&[PropertyName::BrowserName, PropertyName::IsMobile])?;
+    drop(res);
+
+    let handle = std::thread::spawn(move || {
+        (
+            snapshot.get(PropertyName::BrowserName).map(String::from),
+            snapshot.get(PropertyName::IsMobile).map(String::from),
+            snapshot.get(PropertyName::DeviceType).map(String::from),
+        )
+    });
+    let (browser_name, is_mobile, device_type) = handle.join().unwrap();
+
+    assert_eq!(browser_name, Some(String::from("Mobile Safari")));
+    assert_eq!(is_mobile, Some(String::from("True")));
+    assert_eq!(device_type, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_dataset_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let stats = manager.dataset_stats()?;
+
+    assert!(stats.profiles > 0);
+    assert!(stats.values > 0);
+    assert!(stats.strings > 0);
+    assert!(stats.nodes > 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_product_name() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    assert!(!manager.product_name()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_data_file_info() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let info = manager.data_file_info()?;
+
+    assert_eq!(info.tier, manager.data_tier()?);
+    assert!(!info.tier.is_empty());
+    assert_eq!(info.published, manager.data_published_date()?);
+    assert!(info.published.year >= 2020, "{:?}", info.published);
+    assert!((1..=12).contains(&info.published.month) && (1..=31).contains(&info.published.day));
+    assert!(manager.data_next_update_date()? >= info.published);
+    assert_eq!(manager.data_file_version()?.split('.').count(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_properties_by_category() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let categories = manager.properties_by_category()?;
+
+    let browser = categories.get("Browser").ok_or("Browser category should exist")?;
+    assert!(browser.iter().any(|name| name == "BrowserName"), "{:?}", categories);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_evidence_with_raw_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let mut evidence = Evidence::new(2)?;
+    evidence.add(EvidenceName::UserAgent.as_str(), IPHONE_UA)?;
+    // "Café" encoded as Latin-1, which is not valid UTF-8
+    evidence.add_bytes("x-client-name", b"Caf\xe9")?;
+
+    let res = manager.detect_evidence(&evidence)?;
+
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?,
+        Some(String::from("Mobile Safari"))
+    );
+    assert!(evidence.add_bytes("x-client-name", b"a\0b").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_property_macro() {
+    assert_eq!(crate::property!("BrowserName"), PropertyName::BrowserName);
+    assert_eq!(crate::property!("IsMobile"), PropertyName::IsMobile);
+    assert_eq!(crate::property!("UserAgents"), PropertyName::UserAgents);
+}
+
+#[test]
+fn test_get_typed() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let values = res.get_typed(&[PropertyName::IsMobile, PropertyName::BrowserName])?;
+
+    assert_eq!(
+        values,
+        vec![
+            (PropertyName::IsMobile, TypedValue::Bool(true)),
+            (
+                PropertyName::BrowserName,
+                TypedValue::Str(String::from("Mobile Safari"))
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_detect_from_headers() -> Result<(), Box<dyn std::error::Error>> {
+    use http::{HeaderMap, HeaderName, HeaderValue};
+
+    let manager = test_manager()?;
+    let mut headers = HeaderMap::new();
+    headers.insert(HeaderName::from_static("x-request-id"), HeaderValue::from_static("42"));
+    assert!(matches!(
+        manager.detect_from_headers(&headers),
+        Err(FiftyOneDegreesError::AssertionError(Operation::CreateEvidence, _))
+    ));
+
+    headers.insert(HeaderName::from_static("user-agent"), HeaderValue::from_static(IPHONE_UA));
+    headers.insert(
+        HeaderName::from_static("sec-ch-ua-platform"),
+        HeaderValue::from_bytes(b"\xff")?,
+    );
+    let res = manager.detect_from_headers(&headers)?;
+
+    assert_eq!(res.get_value_as_string(PropertyName::PlatformName)?, Some(String::from("iOS")));
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_detect_json() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let sec_ch_ua = "\"Safari\";v=\"15\", \"Mobile Safari\";v=\"15\", \"Chromium\";v=\"110\"";
+
+    let json_res = manager.detect_json(&serde_json::json!({
+        "user-agent": IPHONE_UA,
+        "sec-ch-ua": sec_ch_ua,
+        "x-request-count": 3,
+    }))?;
+    let res = manager.detect(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::SecChUa.value(sec_ch_ua),
+    ])?;
+
+    for property_name in [
+        PropertyName::BrowserName,
+        PropertyName::DeviceType,
+        PropertyName::PlatformName,
+    ] {
+        assert_eq!(
+            json_res.get_value_as_string(property_name)?,
+            res.get_value_as_string(property_name)?
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_insufficient_handles_suggests_lower_concurrency() {
+    let err = Manager::init_status_to_error(
+        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INSUFFICIENT_HANDLES,
+    );
+
+    assert!(matches!(
+        err,
+        FiftyOneDegreesError::InternalApiError(
+            Operation::InitManager,
+            StatusCode::InsufficientHandles,
+            _,
+            msg,
+        ) if msg.contains("concurrency")
+    ));
+    assert!(err.to_string().contains("concurrency"), "{}", err);
+}
+
+#[test]
+fn test_is_overridden() -> Result<(), Box<dyn std::error::Error>> {
+    let conf = ManagerConfig {
+        property_names: Some(vec![PropertyName::HardwareModel, PropertyName::BrowserName]),
+        allow_overrides: true,
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    };
+    let manager = Manager::new(conf)?;
+
+    let mut evidence = Evidence::new(2)?;
+    evidence.add(EvidenceName::UserAgent.as_str(), IPHONE_UA)?;
+    evidence.add_override(PropertyName::HardwareModel, "iPhone 13")?;
+    let res = manager.detect_evidence(&evidence)?;
+
+    assert!(res.is_overridden(PropertyName::HardwareModel)?);
+    assert!(!res.is_overridden(PropertyName::BrowserName)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_lazy_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let conf = ManagerConfig {
+        property_names: Some(vec![PropertyName::BrowserName]),
+        lazy_properties: true,
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    };
+    let manager = Manager::new(conf)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?,
+        Some(String::from("Mobile Safari"))
+    );
+    assert_eq!(
+        res.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+    // Detections after the reload use the extended property set directly
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(
+        res.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_property_name_to_c_key() {
+    assert_eq!(
+        PropertyName::BrowserName.to_c_key().ok().as_deref(),
+        Some("BrowserName")
+    );
+    assert_eq!(
+        PropertyName::Custom("Custom").to_c_key().ok().as_deref(),
+        Some("Custom")
+    );
+    assert!(matches!(
+        PropertyName::Custom("a\0b").to_c_key(),
+        Err(FiftyOneDegreesError::CStringCreationError(_))
+    ));
+    assert!(PropertyName::Custom("").to_c_key().is_err());
+}
+
+#[test]
+fn test_evidence_iter() -> Result<(), Box<dyn std::error::Error>> {
+    let mut evidence = Evidence::new(2)?;
+    evidence.add(EvidenceName::UserAgent.as_str(), IPHONE_UA)?;
+    evidence.add(EvidenceName::SecChPlatform.as_str(), "\"iOS\"")?;
+
+    let pairs = evidence.iter().collect::<Vec<_>>();
+
+    assert_eq!(
+        pairs,
+        vec![("user-agent", IPHONE_UA), ("sec-ch-platform", "\"iOS\"")]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_hardware_model_variants() -> Result<(), Box<dyn std::error::Error>> {
+    let conf = ManagerConfig {
+        property_names: Some(vec![
+            PropertyName::HardwareVendor,
+            PropertyName::HardwareModelVariants,
+        ]),
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    };
+    let manager = Manager::new(conf)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let variants = res.hardware_model_variants()?;
+    assert!(variants.len() > 1, "{:?}", variants);
+
+    let vendor_models = res.hardware_vendor_models()?;
+    assert_eq!(vendor_models.len(), variants.len());
+    assert!(vendor_models.iter().all(|(vendor, _)| vendor == "Apple"));
+
+    Ok(())
+}
+
+#[test]
+fn test_get_values() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::HardwareModelVariants])
+        .build();
+    let manager = Manager::new(config)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let values = res.get_values(PropertyName::HardwareModelVariants)?;
+    let joined = res.get_value_as_string(PropertyName::HardwareModelVariants)?;
+
+    assert!(values.len() > 1, "{:?}", values);
+    assert!(values.iter().all(|value| !value.is_empty() && value.trim() == value));
+    assert_eq!(Some(values.join(", ")), joined);
+    assert!(res.get_values(PropertyName::PlatformName)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_value_sources() -> Result<(), Box<dyn std::error::Error>> {
+    let conf = ManagerConfig {
+        property_names: Some(vec![PropertyName::HardwareModelVariants]),
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    };
+    let manager = Manager::new(conf)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let sources = res.value_sources(PropertyName::HardwareModelVariants)?;
+
+    assert!(sources.len() > 1, "{:?}", sources);
+    assert!(sources.iter().all(|(_, profile_id)| *profile_id != 0));
+
+    Ok(())
+}
+
+#[test]
+fn test_new_with_fallback() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = Manager::new_with_fallback(
+        std::path::Path::new("missing-enterprise.hash"),
+        std::path::Path::new("data.hash"),
+        Some(&[PropertyName::BrowserName]),
+    )?;
+
+    assert_eq!(
+        manager.source_file(),
+        std::path::Path::new("data.hash").canonicalize()?
+    );
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?,
+        Some(String::from("Mobile Safari"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_capabilities() -> Result<(), Box<dyn std::error::Error>> {
+    let conf = ManagerConfig {
+        property_names: Some(vec![
+            PropertyName::HasTouchScreen,
+            PropertyName::IsMobile,
+            PropertyName::IsTv,
+            PropertyName::BrowserName,
+        ]),
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    };
+    let manager = Manager::new(conf)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let capabilities = res.capabilities()?;
+
+    assert_eq!(capabilities.get(&PropertyName::HasTouchScreen), Some(&true));
+    assert_eq!(capabilities.get(&PropertyName::IsMobile), Some(&true));
+    assert_eq!(capabilities.get(&PropertyName::IsTv), Some(&false));
+    assert!(!capabilities.contains_key(&PropertyName::BrowserName));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_without_results() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let res = manager.detect(&[EvidenceName::Custom("x-irrelevant").value("value")]);
+
+    assert!(matches!(
+        res,
+        Err(FiftyOneDegreesError::AssertionError(Operation::ApplyEvidence, _))
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "structured-hints")]
+#[test]
+fn test_detect_structured_hints() -> Result<(), Box<dyn std::error::Error>> {
+    use base64::Engine;
+
+    let manager = test_manager()?;
+    let sec_ch_ua = "\"Safari\";v=\"15\", \"Mobile Safari\";v=\"15\", \"Chromium\";v=\"110\"";
+    let bundle = r#"ua="\"Safari\";v=\"15\", \"Mobile Safari\";v=\"15\", \"Chromium\";v=\"110\"", sec-ch-platform="\"iOS\"""#;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bundle);
+
+    let hints_res = manager.detect_structured_hints(&encoded)?;
+    let res = manager.detect(&[
+        EvidenceName::SecChUa.value(sec_ch_ua),
+        EvidenceName::SecChPlatform.value("\"iOS\""),
+    ])?;
+
+    for property_name in [PropertyName::BrowserName, PropertyName::PlatformName] {
+        assert_eq!(
+            hints_res.get_value_as_string(property_name)?,
+            res.get_value_as_string(property_name)?
+        );
+    }
+    assert!(matches!(
+        manager.detect_structured_hints("not base64!"),
+        Err(FiftyOneDegreesError::AssertionError(Operation::CreateEvidence, _))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_component_confidence() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let confidence = res.component_confidence("HardwarePlatform")?;
+    assert!(confidence.is_some_and(|c| c > 0.9), "{:?}", confidence);
+    assert_eq!(res.component_confidence("NoSuchComponent")?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_nodes_evaluated() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let nodes = res.nodes_evaluated();
+    assert!((1..500).contains(&nodes), "{}", nodes);
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_evidence() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let issues = manager.validate_evidence(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::SecChUa.value(""),
+    ]);
+
+    assert_eq!(
+        issues,
+        vec![EvidenceIssue::EmptyValue {
+            key: String::from("sec-ch-ua")
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_detect() -> Result<(), Box<dyn std::error::Error>> {
+    let android_ua = "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36";
+    let manager = test_manager()?;
+    let barrier = std::sync::Barrier::new(8);
+
+    std::thread::scope(|scope| {
+        let detections = (0..8)
+            .map(|i| {
+                let (ua, platform_name) = if i % 2 == 0 {
+                    (IPHONE_UA, "iOS")
+                } else {
+                    (android_ua, "Android")
+                };
+                let manager = &manager;
+                let barrier = &barrier;
+                scope.spawn(move || -> Result<(), String> {
+                    barrier.wait();
+                    for _ in 0..1_000 {
+                        let res = manager
+                            .detect(&[EvidenceName::UserAgent.value(ua)])
+                            .map_err(|e| e.to_string())?;
+                        let platform = res
+                            .get_value_as_string(PropertyName::PlatformName)
+                            .map_err(|e| e.to_string())?;
+                        assert_eq!(platform.as_deref(), Some(platform_name));
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+        detections
+            .into_iter()
+            .try_for_each(|detection| detection.join().expect("Detection thread should not panic"))
+    })?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_temp_file_error_names_dir() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = std::env::temp_dir().join("fiftyonedegrees-read-only");
+    std::fs::create_dir_all(&temp_dir)?;
+    std::fs::set_permissions(&temp_dir, std::fs::Permissions::from_mode(0o555))?;
+    // Permissions are not enforced for privileged users
+    if std::fs::write(temp_dir.join("probe"), "").is_ok() {
+        std::fs::remove_file(temp_dir.join("probe"))?;
+        return Ok(());
+    }
+    let result = Manager::new(ManagerConfig {
+        temp_dir: Some(temp_dir.clone()),
+        ..ManagerConfig::new(std::path::Path::new("data.hash"))
+    });
+
+    assert!(matches!(
+        result,
+        Err(FiftyOneDegreesError::TempFileError { ref dir }) if *dir == temp_dir
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_report() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let report = manager.detect_report(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(report.browser.map(|browser| browser.name).as_deref(), Some("Mobile Safari"));
+    assert_eq!(report.device_type, Some(DeviceType::SmartPhone));
+    assert_eq!(report.is_mobile, Some(true));
+    let platform = report.platform.ok_or("Platform should be detected")?;
+    assert_eq!(platform.name, "iOS");
+    assert_eq!(platform.version.as_deref(), Some("15.2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_repeated_reads_are_stable() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    for _ in 0..100_000 {
+        assert_eq!(
+            res.get_value_as_string(PropertyName::BrowserName)?.as_deref(),
+            Some("Mobile Safari")
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_hint_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let android_ua = "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36";
+    let evidence = [
+        EvidenceName::UserAgent.value(android_ua),
+        EvidenceName::Custom("sec-ch-ua-platform").value("\"iOS\""),
+    ];
+    let platform_name = |hint_policy| -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+            .property_names(&[PropertyName::PlatformName])
+            .hint_policy(hint_policy)
+            .build();
+        let manager = Manager::new(config)?;
+        Ok(manager
+            .detect(&evidence)?
+            .get_value_as_string(PropertyName::PlatformName)?)
+    };
+
+    assert_eq!(platform_name(HintPolicy::PreferHints)?.as_deref(), Some("iOS"));
+    assert_eq!(platform_name(HintPolicy::PreferUserAgent)?.as_deref(), Some("Android"));
+    // The client hint wins when both are passed
+    assert_eq!(platform_name(HintPolicy::Combine)?.as_deref(), Some("iOS"));
+
+    Ok(())
+}
+
+#[test]
+fn test_hint_policy_partial_hints() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::BrowserName, PropertyName::PlatformName])
+        .hint_policy(HintPolicy::PreferHints)
+        .build();
+    let manager = Manager::new(config)?;
+
+    // Without the platform hints, which the User-Agent would have given
+    let result = manager.detect(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::SecChUa.value("\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\""),
+    ])?;
+
+    // The browser still resolves from the hints, the dropped User-Agent gives no platform
+    let browser_name = result.get_value_as_string(PropertyName::BrowserName)?;
+    assert!(browser_name.is_some());
+    assert_ne!(browser_name.as_deref(), Some("Mobile Safari"));
+    assert_ne!(
+        result.get_value_as_string(PropertyName::PlatformName)?.as_deref(),
+        Some("iOS")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_fingerprint() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let other_iphone_ua = "Mozilla/5.0 (iPhone; CPU iPhone OS 15_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.2 Mobile/19C56 Safari/604.1";
+
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let other_res = manager.detect(&[EvidenceName::UserAgent.value(other_iphone_ua)])?;
+
+    assert_eq!(res.fingerprint()?, other_res.fingerprint()?);
+
+    Ok(())
+}
+
+#[test]
+fn test_bool_parsing() {
+    for (raw, expected) in [
+        ("true", true),
+        ("True", true),
+        ("1", true),
+        ("YES", true),
+        ("false", false),
+        ("FALSE", false),
+        ("0", false),
+        ("No", false),
+    ] {
+        assert_eq!(BoolParsing::Lenient.parse(raw), Some(expected), "{}", raw);
+    }
+    assert_eq!(BoolParsing::Strict.parse("True"), Some(true));
+    assert_eq!(BoolParsing::Strict.parse("yes"), None);
+    assert_eq!(BoolParsing::Lenient.parse("Unknown"), None);
+}
+
+#[test]
+fn test_for_each_value() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let mut names = Vec::new();
+    res.for_each_value(&mut |name, _| names.push(name.to_string()))?;
+
+    assert_eq!(names.len(), 5, "{:?}", names);
+
+    Ok(())
+}
+
+#[test]
+fn test_emulation() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::IsEmulatingDesktop, PropertyName::IsEmulatingDevice])
+        .build();
+    let manager = Manager::new(config)?;
+    // Samsung Internet with "Desktop site" enabled
+    let desktop_mode_ua = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/22.0 Chrome/111.0.5563.116 Safari/537.36";
+
+    let res = manager.detect(&[EvidenceName::UserAgent.value(desktop_mode_ua)])?;
+    assert_eq!(res.emulation()?, Emulation::Desktop);
+
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(res.emulation()?, Emulation::None);
+
+    Ok(())
+}
+
+#[test]
+fn test_file_changed() -> Result<(), Box<dyn std::error::Error>> {
+    // Touches a copy, the shared data file is read by the other tests
+    let copy = std::env::temp_dir().join(format!("{}-changed.hash", std::process::id()));
+    std::fs::copy("data.hash", &copy)?;
+    let manager = Manager::new(ManagerConfig::new(&copy))?;
+    let unchanged = manager.file_changed()?;
+
+    let file = std::fs::OpenOptions::new().append(true).open(&copy)?;
+    file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(1))?;
+    let changed = manager.file_changed()?;
+    drop(manager);
+    std::fs::remove_file(&copy)?;
+
+    assert!(!unchanged);
+    assert!(changed);
+
+    Ok(())
+}
+
+#[test]
+fn test_reload_from_file() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let before = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let data = std::fs::read("data.hash")?;
+    let copy = std::env::temp_dir().join(format!("{}-reload.hash", std::process::id()));
+    std::fs::write(&copy, &data)?;
+    let copy = copy.canonicalize()?;
+    let reloaded = manager.reload_from_file(&copy);
+    let after = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)]);
+    std::fs::remove_file(&copy)?;
+    reloaded?;
+
+    assert_eq!(manager.source_file(), copy);
+    assert_eq!(
+        after?.get_value_as_string(PropertyName::PlatformName)?,
+        before.get_value_as_string(PropertyName::PlatformName)?
+    );
+
+    // A truncated file fails to load, the previous data set is kept
+    let truncated = std::env::temp_dir().join(format!("{}-truncated.hash", std::process::id()));
+    std::fs::write(&truncated, &data[..data.len() / 2])?;
+    let result = manager.reload_from_file(&truncated);
+    std::fs::remove_file(&truncated)?;
+
+    assert!(result.is_err());
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(res.get_value_as_string(PropertyName::PlatformName)?, Some(String::from("iOS")));
+
+    Ok(())
+}
+
+#[test]
+fn test_reload_from_file_checks_config() -> Result<(), Box<dyn std::error::Error>> {
+    let tier = test_manager()?.product_name()?;
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName])
+        .require_tier(&tier)
+        .build();
+    let mut manager = Manager::new(config)?;
+    manager.reload_from_file("data.hash")?;
+
+    // As if the file had been replaced by one of another tier
+    manager.set_require_tier("NoSuchTier");
+    let result = manager.reload_from_file("data.hash");
+
+    assert!(
+        matches!(result, Err(FiftyOneDegreesError::TierMismatch { ref actual, .. }) if *actual == tier),
+        "{:?}",
+        result
+    );
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(res.get_value_as_string(PropertyName::PlatformName)?, Some(String::from("iOS")));
+
+    Ok(())
+}
+
+#[test]
+fn test_value_parse_error() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let err = res
+        .get_value_as_i32(PropertyName::BrowserName)
+        .expect_err("Browser name should not parse as integer");
+
+    assert!(matches!(
+        &err,
+        FiftyOneDegreesError::ValueParse { raw, target: "integer", .. } if raw == "Mobile Safari"
+    ));
+    assert!(err.to_string().contains("Mobile Safari"));
+
+    Ok(())
+}
+
+#[test]
+fn test_error_kind() -> Result<(), Box<dyn std::error::Error>> {
+    let missing = Manager::new(ManagerConfig::new(std::path::Path::new("missing.hash")))
+        .err()
+        .ok_or("Missing data file should fail to load")?;
+    assert_eq!(missing.kind(), ErrorKind::DataFile);
+
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let err = res.get_value_as_i32(PropertyName::BrowserName).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let err = FiftyOneDegreesError::ManagerPoisoned(StatusCode::CorruptData);
+    assert_eq!(err.kind(), ErrorKind::Internal(Some(StatusCode::CorruptData)));
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_values() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[
+            PropertyName::IsMobile,
+            PropertyName::ScreenPixelsWidth,
+            PropertyName::PlatformName,
+        ])
+        .bool_parsing(BoolParsing::Strict)
+        .build();
+    let manager = Manager::new(config)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(res.get_value_as_bool(PropertyName::IsMobile)?, Some(true));
+    let width = res.get_value_as_i32(PropertyName::ScreenPixelsWidth)?;
+    assert!(width.is_some_and(|width| width >= 320), "{:?}", width);
+    let width = res.get_value_as_f64(PropertyName::ScreenPixelsWidth)?;
+    assert!(width.is_some_and(|width| width >= 320.0), "{:?}", width);
+    assert!(matches!(
+        res.get_value_as_bool(PropertyName::PlatformName),
+        Err(FiftyOneDegreesError::ValueParse { target: "boolean", .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_unknown_values_unfiltered() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::BrowserName])
+        .filter_unknown_values(false)
+        .build();
+    let manager = Manager::new(config)?;
+
+    let res = manager.detect(&[EvidenceName::UserAgent.value("not a user agent")])?;
+
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?.as_deref(),
+        Some("Unknown")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_device_id_components() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::DeviceId])
+        .build();
+    let manager = Manager::new(config)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let components = res.device_id_components()?;
+
+    assert!(components.iter().all(|&id| id != 0), "{:?}", components);
+
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_reads_do_not_mix() -> Result<(), Box<dyn std::error::Error>> {
+    let android_ua = "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36";
+
+    std::thread::scope(|scope| {
+        let readers = [(IPHONE_UA, "iOS"), (android_ua, "Android")].map(|(ua, platform_name)| {
+            scope.spawn(move || -> Result<(), String> {
+                let manager = test_manager().map_err(|e| e.to_string())?;
+                let res = manager
+                    .detect(&[EvidenceName::UserAgent.value(ua)])
+                    .map_err(|e| e.to_string())?;
+                let read = |name| res.get_value_as_string(name).map_err(|e| e.to_string());
+                let browser_name = read(PropertyName::BrowserName)?;
+                for _ in 0..10_000 {
+                    assert_eq!(read(PropertyName::PlatformName)?.as_deref(), Some(platform_name));
+                    assert_eq!(read(PropertyName::BrowserName)?, browser_name);
+                }
+                Ok(())
+            })
+        });
+        readers
+            .into_iter()
+            .try_for_each(|reader| reader.join().expect("Reader thread should not panic"))
+    })?;
+
+    Ok(())
+}
+
+#[test]
+fn test_new_from_copy() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = Manager::new_from_copy(
+        std::path::Path::new("data.hash"),
+        &std::env::temp_dir(),
+        Some(&[PropertyName::BrowserName]),
+    )?;
+    let copy = manager.source_file().to_path_buf();
+    assert!(copy.starts_with(std::env::temp_dir().canonicalize()?));
+
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?.as_deref(),
+        Some("Mobile Safari")
+    );
+
+    drop(res);
+    drop(manager);
+    assert!(!copy.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_same_device() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let other_build_ua = IPHONE_UA.replace("Mobile/15E148", "Mobile/19C56");
+    let android_ua = "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36";
+
+    assert!(manager.same_device(IPHONE_UA, &other_build_ua)?);
+    assert!(!manager.same_device(IPHONE_UA, android_ua)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_screen_resolution() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::ScreenPixelsWidth, PropertyName::ScreenPixelsHeight])
+        .build();
+    let manager = Manager::new(config)?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let Resolution { width, height } = res
+        .screen_resolution()?
+        .ok_or("iPhone should have a screen resolution")?;
+
+    assert!(width >= 320 && height > width, "{}x{}", width, height);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_into_report() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let android_ua = "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36";
+    let mut report = DeviceReport::default();
+
+    for i in 0..10_000 {
+        let (ua, platform_name) = if i % 2 == 0 {
+            (IPHONE_UA, "iOS")
+        } else {
+            (android_ua, "Android")
+        };
+        manager.detect_into_report(&[EvidenceName::UserAgent.value(ua)], &mut report)?;
+        assert_eq!(
+            report.platform.as_ref().map(|platform| platform.name.as_str()),
+            Some(platform_name)
+        );
+    }
+    assert_eq!(report, manager.detect_report(&[EvidenceName::UserAgent.value(android_ua)])?);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_ai_crawler() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::IsArtificialIntelligence])
+        .build();
+    let manager = Manager::new(config)?;
+    let gptbot_ua = "Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; GPTBot/1.2; +https://openai.com/gptbot)";
+
+    assert!(manager.detect_ai_crawler(gptbot_ua)?);
+    assert!(!manager.detect_ai_crawler(IPHONE_UA)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_raw() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let res = manager.detect_raw(&[("user-agent", IPHONE_UA), ("x-irrelevant", "y")])?;
+
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?.as_deref(),
+        Some("Mobile Safari")
+    );
+
+    // Header names are lowercased however they were spelled
+    let res = manager.detect_raw(&[("User-Agent", IPHONE_UA)])?;
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?.as_deref(),
+        Some("Mobile Safari")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_evidence_key_case() -> Result<(), Box<dyn std::error::Error>> {
+    let mut evidence = Evidence::new(2)?;
+    evidence.add("User-Agent", IPHONE_UA)?;
+    evidence.add_bytes("SEC-CH-UA-Platform", b"\"iOS\"")?;
+
+    let keys = evidence.iter().map(|(key, _)| key).collect::<Vec<_>>();
+    assert_eq!(keys, ["user-agent", "sec-ch-ua-platform"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_required_property_missing() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::BrowserName, PropertyName::Custom("NoSuchProperty")])
+        .strict_properties(true)
+        .build();
+
+    let result = Manager::new(config);
+
+    assert!(matches!(
+        result,
+        Err(FiftyOneDegreesError::RequiredPropertyMissing(ref missing)) if missing == &["NoSuchProperty"]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_available_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::BrowserName, PropertyName::Custom("NoSuchProperty")])
+        .build();
+    let manager = Manager::new(config)?;
+
+    assert_eq!(manager.available_properties()?, vec![String::from("BrowserName")]);
+    assert!(manager.is_property_available(PropertyName::BrowserName));
+    assert!(!manager.is_property_available(PropertyName::Custom("NoSuchProperty")));
+    assert!(!manager.is_property_available(PropertyName::PlatformName));
+
+    let all = Manager::new(ManagerConfig::new(std::path::Path::new("data.hash")))?;
+    let available = all.available_properties()?;
+    assert!(available.iter().any(|name| name == "PlatformName"));
+    assert!(!available.iter().any(|name| name == "NoSuchProperty"));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_owned() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let evidence: Vec<(EvidenceName, String)> = [IPHONE_UA]
+        .iter()
+        .map(|ua| (EvidenceName::UserAgent, ua.to_string()))
+        .collect();
+    let result = manager.detect_owned(&evidence)?;
+
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_license_info() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let license = manager.license_info()?;
+
+    assert!(license.copyright.contains("51Degrees"));
+
+    Ok(())
+}
+
+#[test]
+fn test_platform_family() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(result.platform_family()?, PlatformFamily::IOS);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_from_fd() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::fd::AsRawFd;
+
+    let file = std::fs::File::open("data.hash")?;
+    let config = ManagerConfig::builder(std::path::Path::new(""))
+        .property_names(&[PropertyName::PlatformName, PropertyName::IsMobile])
+        .build();
+    let manager = Manager::from_fd(file.as_raw_fd(), config)?;
+    drop(file);
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+    assert!(manager.file_changed().is_err());
+    assert!(manager.reload_from_file("data.hash").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_memoize_lists() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::HardwareModelVariants])
+        .memoize_lists(true)
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert!(!result.is_memoized(PropertyName::HardwareModelVariants));
+    let first = result.hardware_model_variants()?;
+    assert!(result.is_memoized(PropertyName::HardwareModelVariants));
+    let second = result.hardware_model_variants()?;
+
+    assert_eq!(first, second);
+
+    Ok(())
+}
+
+#[test]
+fn test_to_response_headers() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let headers = result.to_response_headers(&[
+        (PropertyName::IsMobile, "X-Device-Mobile"),
+        (PropertyName::DeviceType, "X-Device-Type"),
+    ])?;
+
+    assert!(headers.contains(&(String::from("X-Device-Mobile"), String::from("True"))));
+
+    Ok(())
+}
+
+#[test]
+fn test_lenient_evidence() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName])
+        .lenient_evidence(true)
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::SecChPlatform.value("\"iOS\0\""),
+    ])?;
+
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+    assert_eq!(result.warnings().len(), 1);
+    assert!(matches!(
+        result.warnings()[0],
+        FiftyOneDegreesError::CStringCreationError(_)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_lenient_evidence_raw() -> Result<(), Box<dyn std::error::Error>> {
+    let headers = [("user-agent", IPHONE_UA), ("sec-ch-ua-platform", "\"iOS\0\"")];
+    assert!(test_manager()?.detect_raw(&headers).is_err());
+
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName])
+        .lenient_evidence(true)
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect_raw(&headers)?;
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+    assert_eq!(result.warnings().len(), 1);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::json!({ "user-agent": IPHONE_UA, "sec-ch-ua-platform": "\"iOS\0\"" });
+        assert_eq!(manager.detect_json(&json)?.warnings().len(), 1);
+    }
+    assert!(manager.detect_raw(&[]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_native_device() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[
+            PropertyName::NativeBrand,
+            PropertyName::NativeModel,
+            PropertyName::NativeName,
+            PropertyName::NativePlatform,
+        ])
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(
+        "Mozilla/5.0 (Linux; Android 12; SM-G991B) AppleWebKit/537.36 (KHTML, like Gecko) \
+         Chrome/108.0.0.0 Mobile Safari/537.36",
+    )])?;
+    let native = result.native_device()?.ok_or("native device fields are absent")?;
+
+    assert_eq!(native.brand.as_deref(), Some("Samsung"));
+    assert!(native.model.is_some_and(|model| model.contains("SM-G991B")));
+
+    Ok(())
+}
+
+#[test]
+fn test_overrides_enabled() -> Result<(), Box<dyn std::error::Error>> {
+    for allow_overrides in [true, false] {
+        let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+            .property_names(&[PropertyName::HardwareModel])
+            .allow_overrides(allow_overrides)
+            .build();
+        let manager = Manager::new(config)?;
+
+        assert_eq!(manager.overrides_enabled(), allow_overrides);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_get_values_with_difference() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::HardwareVendor])
+        .build();
+    let manager = Manager::new(config)?;
+    let android_ua = "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36";
+
+    // Conflicting headers, each matching a different hardware profile
+    let result = manager.detect_raw(&[("user-agent", IPHONE_UA), ("device-stock-ua", android_ua)])?;
+    let values = result.get_values_with_difference(PropertyName::HardwareVendor)?;
+
+    assert!(values.len() >= 2, "expected several candidates, got {:?}", values);
+    assert!(values.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_metrics_only() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let metrics = manager.detect_metrics_only(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert!(metrics.iterations > 0);
+    assert!(metrics.difference >= 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_with_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let result = manager.detect_with_properties(
+        &[EvidenceName::UserAgent.value(IPHONE_UA)],
+        &[PropertyName::PlatformName, PropertyName::IsMobile],
+    )?;
+
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+    assert_eq!(result.get_value_as_bool(PropertyName::IsMobile)?, Some(true));
+    assert!(matches!(
+        result.get_value_as_string(PropertyName::BrowserName),
+        Err(FiftyOneDegreesError::PropertyNotExposed(ref name)) if name == "BrowserName"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_status_code() {
+    let error = Manager::init_status_to_error(
+        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_NOT_FOUND,
+    );
+
+    assert!(matches!(
+        error,
+        FiftyOneDegreesError::InternalApiError(Operation::InitManager, StatusCode::FileNotFound, _, _)
+    ));
+    assert_eq!(StatusCode::from_raw(u32::MAX), StatusCode::Unknown(u32::MAX));
+}
+
+#[test]
+fn test_histogram_device_types() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let desktop_ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    let log = format!("{IPHONE_UA}\n\n{desktop_ua}\n  \n{IPHONE_UA}\n");
+
+    let histogram = manager.histogram_device_types(std::io::Cursor::new(log))?;
+
+    assert_eq!(histogram.get("SmartPhone"), Some(&2));
+    assert_eq!(histogram.get("Desktop"), Some(&1));
+    assert_eq!(histogram.values().sum::<u64>(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_performance_profiles() -> Result<(), Box<dyn std::error::Error>> {
+    for profile in [PerformanceProfile::LowMemory, PerformanceProfile::InMemory] {
+        let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+            .property_names(&[PropertyName::PlatformName])
+            .performance_profile(profile)
+            .concurrency(4)
+            .build();
+        let manager = Manager::new(config)?;
+        let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+        assert_eq!(
+            res.get_value_as_string(PropertyName::PlatformName)?,
+            Some(String::from("iOS")),
+            "{:?}",
+            profile
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_runtime_config() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::current_dir()?.to_string_lossy().into_owned();
+    let data_file_path = format!("{}/{}", dir, "data.hash");
+    let mut property_names = vec![PropertyName::PlatformName];
+    property_names.push(PropertyName::IsMobile);
+
+    let manager = Manager::new(ManagerConfig {
+        property_names: Some(property_names),
+        ..ManagerConfig::new(data_file_path)
+    })?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(res.get_value_as_string(PropertyName::PlatformName)?, Some(String::from("iOS")));
+    assert_eq!(manager.requested_properties_string(), "PlatformName,IsMobile");
+
+    Ok(())
+}
+
+#[test]
+fn test_empty_data_file() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("{}-empty.hash", std::process::id()));
+    std::fs::write(&path, b"")?;
+
+    let result = Manager::new(ManagerConfig::new(&path));
+    std::fs::remove_file(&path)?;
+
+    assert!(matches!(
+        result,
+        Err(FiftyOneDegreesError::AssertionError(Operation::ReadDataFile, "file is empty"))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_directory_data_file() {
+    let result = Manager::new(ManagerConfig::new(std::env::temp_dir()));
+
+    assert!(matches!(
+        result,
+        Err(FiftyOneDegreesError::AssertionError(Operation::ReadDataFile, msg)) if msg.contains("directory")
+    ));
+}
+
+#[test]
+fn test_detect_brands() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let from_brands = manager.detect_brands(IPHONE_UA, &[("Safari", "15"), ("Chromium", "110")])?;
+    let from_raw = manager.detect(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::SecChUa.value("\"Safari\";v=\"15\", \"Chromium\";v=\"110\""),
+    ])?;
+
+    assert_eq!(from_brands.fingerprint()?, from_raw.fingerprint()?);
+    assert_eq!(
+        from_brands.get_value_as_string(PropertyName::BrowserName)?,
+        from_raw.get_value_as_string(PropertyName::BrowserName)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_resident_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let resident = manager.resident_bytes()?;
+    // The data file size is the estimate available before loading
+    let estimate = std::fs::metadata("data.hash")?.len() as usize;
+
+    assert!(resident > 0);
+    assert!(resident > estimate / 10 && resident < estimate * 10);
+
+    Ok(())
+}
+
+#[test]
+fn test_can_detect() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    assert!(matches!(
+        manager.can_detect(&[]),
+        Err(FiftyOneDegreesError::AssertionError(Operation::CreateEvidence, _))
+    ));
+    manager.can_detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    Ok(())
+}
+
+#[test]
+fn test_unused_requested() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName, PropertyName::CrawlerName])
+        .build();
+    let manager = Manager::new(config)?;
+
+    // A phone is never a crawler
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(result.unused_requested()?, vec![PropertyName::CrawlerName]);
+
+    Ok(())
+}
+
+#[test]
+fn test_form_factors_hint() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let desktop_ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+    let result = manager.detect(&[
+        EvidenceName::UserAgent.value(desktop_ua),
+        EvidenceName::SecChUaFormFactors.value("\"Desktop\""),
+        EvidenceName::SecChUaWow64.value("?0"),
+    ])?;
+
+    assert_eq!(
+        result.get_value_as_string(PropertyName::DeviceType)?,
+        Some(String::from("Desktop"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_match_method() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(result.method()?, MatchMethod::Performance);
+
+    Ok(())
+}
+
+#[test]
+fn test_values_in_order() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[
+            PropertyName::BrowserName,
+            PropertyName::CrawlerName,
+            PropertyName::DeviceType,
+            PropertyName::IsMobile,
+            PropertyName::PlatformName,
+        ])
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let values = result.values_in_order(&[
+        PropertyName::PlatformName,
+        PropertyName::IsMobile,
+        PropertyName::CrawlerName,
+        PropertyName::DeviceType,
+        PropertyName::BrowserName,
+    ])?;
+
+    assert_eq!(
+        values,
+        vec![
+            Some(String::from("iOS")),
+            Some(String::from("True")),
+            None,
+            Some(String::from("SmartPhone")),
+            Some(String::from("Mobile Safari")),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_value_buffer_size() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::HardwareName])
+        .value_buffer_size(512)
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let resizes = value_buffer_resizes();
+    // Lists every iPhone model sharing the User-Agent
+    let hardware_name = result.get_value_as_string(PropertyName::HardwareName)?;
+
+    assert!(hardware_name.is_some_and(|name| name.len() < 512));
+    assert_eq!(value_buffer_resizes(), resizes);
+
+    Ok(())
+}
+
+#[test]
+fn test_long_value_grows_buffer() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::HardwareModelVariants])
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let resizes = value_buffer_resizes();
+    let variants = result.get_value_as_string(PropertyName::HardwareModelVariants)?;
+
+    // Longer than the default buffer, read again into a grown one
+    let expected = result.hardware_model_variants()?.join(", ");
+    assert!(expected.len() > 128, "{}", expected);
+    assert_eq!(variants, Some(expected));
+    assert_eq!(value_buffer_resizes(), resizes + 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_uses_evidence_key() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    assert!(manager.uses_evidence_key("user-agent")?);
+    assert!(!manager.uses_evidence_key("x-nonsense")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_clear() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let mut result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    result.clear();
+
+    assert_eq!(result.get_value_as_string(PropertyName::PlatformName)?, None);
+    assert_eq!(result.get_value_as_bool(PropertyName::IsMobile)?, None);
+    assert_eq!(result.match_metrics().iterations, 0);
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ranks() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[
+            PropertyName::BrowserRank,
+            PropertyName::PlatformRank,
+            PropertyName::HardwareRank,
+        ])
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let ranks = result.ranks()?;
+
+    assert!(ranks.browser.is_some_and(|rank| rank > 0));
+    assert!(ranks.platform.is_some_and(|rank| rank > 0));
+    assert!(ranks.hardware.is_some_and(|rank| rank > 0));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_audited() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let (result, echo) = manager.detect_audited(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::SecChPlatform.value("\"iOS\""),
+    ])?;
+
+    assert_eq!(
+        echo,
+        vec![
+            (String::from("user-agent"), String::from(IPHONE_UA)),
+            (String::from("sec-ch-platform"), String::from("\"iOS\"")),
+        ]
+    );
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_evidence_name_case() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let ua = EvidenceName::UserAgent.value(IPHONE_UA);
+    let brands = "\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\"";
+
+    let (_, custom) = manager.detect_audited(&[ua.clone(), EvidenceName::Custom("SEC-CH-UA").value(brands)])?;
+    let (_, known) = manager.detect_audited(&[ua, EvidenceName::SecChUa.value(brands)])?;
+
+    assert_eq!(custom, known);
+    assert_eq!(custom[1].0, "sec-ch-ua");
+
+    Ok(())
+}
+
+#[test]
+fn test_content_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let tag = test_manager()?.content_tag()?;
+    assert_eq!(tag, test_manager()?.content_tag()?);
+
+    // Same data with another file tag, which starts at byte 16 of the header
+    let mut data = std::fs::read("data.hash")?;
+    data[16] = !data[16];
+    let path = std::env::temp_dir().join(format!("{}-retagged.hash", std::process::id()));
+    std::fs::write(&path, &data)?;
+
+    let retagged =
+        Manager::new(ManagerConfig::new(&path)).and_then(|manager| manager.content_tag());
+    std::fs::remove_file(&path)?;
+
+    assert_ne!(tag, retagged?);
+
+    Ok(())
+}
+
+#[test]
+fn test_result_reader() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    let reader = res.reader()?;
+    drop(res);
+
+    let properties = [
+        PropertyName::BrowserName,
+        PropertyName::DeviceType,
+        PropertyName::PlatformName,
+        PropertyName::IsMobile,
+    ];
+    let values: Vec<Option<String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = properties
+            .iter()
+            .map(|&name| {
+                let reader = &reader;
+                scope.spawn(move || reader.get(name).map(String::from))
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    assert_eq!(values[0].as_deref(), Some("Mobile Safari"));
+    assert_eq!(values[1].as_deref(), Some("SmartPhone"));
+    assert_eq!(values[2].as_deref(), Some("iOS"));
+    assert_eq!(values[3].as_deref(), Some("True"));
+    assert_eq!(reader.get_by_name("PlatformName"), Some("iOS"));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_progressive() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let (res, path) = manager.detect_progressive(
+        IPHONE_UA,
+        &[
+            EvidenceName::SecChUa.value("\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\""),
+            EvidenceName::SecChPlatform.value("\"Windows\""),
+        ],
+    )?;
+
+    assert_eq!(path, DetectionPath::Hints);
+    assert_eq!(res.match_metrics().difference, 0);
+    // The iPhone User-Agent would have matched iOS
+    assert_ne!(
+        res.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_progressive_max_difference() -> Result<(), Box<dyn std::error::Error>> {
+    let hints = [
+        EvidenceName::SecChUa.value("\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\""),
+        EvidenceName::SecChPlatform.value("\"Windows\""),
+    ];
+    // The fallback drops the hints, so its result comes from the User-Agent only
+    let manager = |max_difference| {
+        let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+            .property_names(&[PropertyName::PlatformName])
+            .hint_policy(HintPolicy::PreferUserAgent)
+            .progressive_max_difference(max_difference)
+            .build();
+        Manager::new(config)
+    };
+
+    let (res, path) = manager(i32::MAX)?.detect_progressive(IPHONE_UA, &hints)?;
+    assert_eq!(path, DetectionPath::Hints);
+    assert_eq!(res.get_value_as_string(PropertyName::PlatformName)?.as_deref(), Some("Windows"));
+
+    let (res, path) = manager(-1)?.detect_progressive(IPHONE_UA, &hints)?;
+    assert_eq!(path, DetectionPath::UserAgentFallback);
+    assert_eq!(res.get_value_as_string(PropertyName::PlatformName)?.as_deref(), Some("iOS"));
+
+    Ok(())
+}
+
+#[test]
+fn test_release_years() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformReleaseYear, PropertyName::BrowserReleaseYear])
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    // iOS 15 was released in 2021
+    let platform_year = result.platform_release_year()?;
+    assert!(platform_year.is_some_and(|year| (2020..=2022).contains(&year)), "{:?}", platform_year);
+    assert!(result.browser_release_year()?.is_some());
+
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "mmap"))]
+#[test]
+fn test_from_mmap() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName])
+        .build();
+    let manager = Manager::from_mmap(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    assert_eq!(
+        result.get_value_as_string(PropertyName::PlatformName)?,
+        Some(String::from("iOS"))
+    );
+    assert!(!manager.file_changed()?);
+
+    Ok(())
+}
+
+#[test]
+fn test_evidence_contribution() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let contributions = manager.evidence_contribution(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(contributions, vec![(String::from("user-agent"), Contribution::Decisive)]);
+
+    let contributions = manager.evidence_contribution(&[
+        EvidenceName::UserAgent.value(IPHONE_UA),
+        EvidenceName::Custom("x-irrelevant").value("value"),
+    ])?;
+    assert_eq!(contributions[0].1, Contribution::Decisive);
+    assert_eq!(contributions[1], (String::from("x-irrelevant"), Contribution::Ignored));
+
+    Ok(())
+}
+
+#[test]
+fn test_manager_poisoned() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+    let evidence = [EvidenceName::UserAgent.value(IPHONE_UA)];
+
+    // Errors of the input don't poison the manager
+    manager.record_failure(&Manager::init_status_to_error(
+        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_ENCODING_ERROR,
+    ));
+    assert!(!manager.is_poisoned());
+    manager.detect(&evidence)?;
+
+    // A call of the C library which didn't fail leaves the exception unset
+    let mut exception = new_exception();
+    verify_exception(&raw mut exception, Operation::ApplyEvidence)?;
+
+    exception.status = bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_CORRUPT_DATA;
+    let error = verify_exception(&raw mut exception, Operation::ApplyEvidence).unwrap_err();
+    manager.record_failure(&error);
+    assert!(manager.is_poisoned());
+    assert!(matches!(
+        manager.detect(&evidence),
+        Err(FiftyOneDegreesError::ManagerPoisoned(StatusCode::CorruptData))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_as_label_pairs() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::IsMobile, PropertyName::PlatformName, PropertyName::DeviceId])
+        .build();
+    let manager = Manager::new(config)?;
+
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let labels = result.as_label_pairs(&[
+        PropertyName::IsMobile,
+        PropertyName::PlatformName,
+        PropertyName::DeviceId,
+    ])?;
+
+    assert_eq!(
+        labels,
+        vec![
+            (String::from("is_mobile"), String::from("True")),
+            (String::from("platform_name"), String::from("iOS")),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_require_tier() -> Result<(), Box<dyn std::error::Error>> {
+    let actual = test_manager()?.product_name()?;
+
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .require_tier(&actual.to_uppercase())
+        .build();
+    Manager::new(config)?;
+
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .require_tier("Enterprise")
+        .build();
+    let error = Manager::new(config).err().ok_or("The tier should not match")?;
+    let message = error.to_string();
+
+    assert!(matches!(error, FiftyOneDegreesError::TierMismatch { .. }));
+    assert!(message.contains("Enterprise"), "{}", message);
+    assert!(message.contains(&actual), "{}", message);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_cached_evidence() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName])
+        .evidence_cache_size(1)
+        .build();
+    let manager = Manager::new(config)?;
+    let ua = EvidenceName::UserAgent.value(IPHONE_UA);
+    let platform = EvidenceName::SecChPlatform.value("\"iOS\"");
+
+    let first = manager.detect_cached_evidence(&[ua.clone(), platform.clone()])?;
+    let reordered = manager.detect_cached_evidence(&[platform.clone(), ua.clone()])?;
+    assert!(std::sync::Arc::ptr_eq(&first, &reordered));
+    assert_eq!(first.get(PropertyName::PlatformName), Some("iOS"));
+
+    // Evicts the previous evidence set
+    let ua_only = manager.detect_cached_evidence(std::slice::from_ref(&ua))?;
+    assert!(!std::sync::Arc::ptr_eq(&first, &ua_only));
+    let again = manager.detect_cached_evidence(&[ua, platform])?;
+    assert!(!std::sync::Arc::ptr_eq(&first, &again));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_cached_evidence_lru() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::PlatformName])
+        .evidence_cache_size(2)
+        .build();
+    let manager = Manager::new(config)?;
+    let ua = [EvidenceName::UserAgent.value(IPHONE_UA)];
+    let platform = [EvidenceName::SecChPlatform.value("\"iOS\"")];
+    let both = [ua[0].clone(), platform[0].clone()];
+
+    let ua_first = manager.detect_cached_evidence(&ua)?;
+    let platform_first = manager.detect_cached_evidence(&platform)?;
+    // Uses the User-Agent set more often than the cache holds, the platform set stays older
+    for _ in 0..10 {
+        assert!(std::sync::Arc::ptr_eq(&ua_first, &manager.detect_cached_evidence(&ua)?));
+    }
+
+    // Evicts the platform set, the least recently used one
+    manager.detect_cached_evidence(&both)?;
+    assert!(std::sync::Arc::ptr_eq(&ua_first, &manager.detect_cached_evidence(&ua)?));
+    let platform_again = manager.detect_cached_evidence(&platform)?;
+    assert!(!std::sync::Arc::ptr_eq(&platform_first, &platform_again));
+
+    Ok(())
+}
+
+#[test]
+fn test_update_url() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let url = manager.update_url("ABCD1234")?;
+
+    assert!(url.starts_with("https://distributor.51degrees.com/"), "{}", url);
+    assert!(url.contains("LicenseKeys=ABCD1234"), "{}", url);
+    assert!(url.contains(&manager.product_name()?), "{}", url);
+    assert!(manager.update_url("key&more")?.contains("LicenseKeys=key%26more"));
+
+    Ok(())
+}
+
+#[test]
+fn test_resolved_components() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = test_manager()?;
+
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    let resolved = res.resolved_components()?;
+    assert!(resolved.iter().any(|name| name == "HardwarePlatform"), "{:?}", resolved);
+    assert!(resolved.iter().any(|name| name == "SoftwarePlatform"), "{:?}", resolved);
+    assert!(resolved.iter().any(|name| name == "BrowserUA"), "{:?}", resolved);
+
+    // A browser without any platform or device
+    let res = manager.detect(&[EvidenceName::UserAgent.value("Mozilla/5.0 Firefox/120.0")])?;
+    let resolved = res.resolved_components()?;
+    assert!(resolved.iter().any(|name| name == "BrowserUA"), "{:?}", resolved);
+    assert!(!resolved.iter().any(|name| name == "HardwarePlatform"), "{:?}", resolved);
+
+    Ok(())
+}
+
+#[test]
+fn test_client_hint_response() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .client_hint_lifetime(std::time::Duration::from_secs(86400))
+        .build();
+    let manager = Manager::new(config)?;
+
+    let response = manager.client_hint_response()?;
+    let hints: Vec<&str> = response.accept_ch.split(", ").collect();
+
+    assert!(hints.iter().any(|hint| hint.eq_ignore_ascii_case("sec-ch-ua")), "{:?}", hints);
+    for hint in &hints {
+        assert!(hint.to_ascii_lowercase().starts_with("sec-ch-"), "{:?}", hints);
+        assert!(manager.uses_evidence_key(hint)?);
+    }
+    let critical = response.critical_ch.to_ascii_lowercase();
+    assert!(!critical.split(", ").any(|hint| hint == "sec-ch-ua"), "{}", critical);
+    assert_eq!(response.accept_ch_lifetime, 86400);
+    let lifetime = (String::from("Accept-CH-Lifetime"), String::from("86400"));
+    assert!(response.to_headers().contains(&lifetime));
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use super::super::device_detection::{EvidenceName, Manager, PropertyName};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+const IPHONE_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 15_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.2 Mobile/15E148 Safari/604.1";
+
+/// Serves `body` to a single HTTP request, returns the URL to request.
+fn serve_once(body: Vec<u8>) -> Result<String, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/data.hash", listener.local_addr()?);
+    std::thread::spawn(move || -> std::io::Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )?;
+        stream.write_all(&body)
+    });
+    Ok(url)
+}
+
+#[test]
+fn test_from_url() -> Result<(), Box<dyn std::error::Error>> {
+    let url = serve_once(std::fs::read("data.hash")?)?;
+    let cache_path = std::env::temp_dir().join(format!("{}-download.hash", std::process::id()));
+    let property_names = Some(&[PropertyName::BrowserName][..]);
+
+    let manager = Manager::from_url(&url, &cache_path, property_names)?;
+    let result = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+    assert_eq!(
+        result.get_value_as_string(PropertyName::BrowserName)?,
+        Some(String::from("Mobile Safari"))
+    );
+
+    // The server is gone, the cache is used
+    let reused = Manager::from_url(&url, &cache_path, property_names);
+    std::fs::remove_file(&cache_path)?;
+    assert!(reused.is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use super::super::device_detection::{EvidenceName, Manager, ManagerConfig, PropertyName};
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const IPHONE_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 15_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.2 Mobile/15E148 Safari/604.1";
+
+/// Recorder counting the increments of the detection counter.
+#[derive(Default)]
+struct DetectCountRecorder {
+    count: Arc<DetectCount>,
+}
+
+#[derive(Default)]
+struct DetectCount(AtomicU64);
+
+impl CounterFn for DetectCount {
+    fn increment(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn absolute(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+impl Recorder for DetectCountRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        if key.name() == "fiftyone.detect.count" {
+            Counter::from_arc(self.count.clone())
+        } else {
+            Counter::noop()
+        }
+    }
+
+    fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::noop()
+    }
+}
+
+#[test]
+fn test_detect_count() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::BrowserName])
+        .build();
+    let manager = Manager::new(config)?;
+    let recorder = DetectCountRecorder::default();
+
+    metrics::with_local_recorder(&recorder, || {
+        for _ in 0..3 {
+            manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+        }
+        Ok::<_, Box<dyn std::error::Error>>(())
+    })?;
+
+    assert_eq!(recorder.count.0.load(Ordering::Relaxed), 3);
+
+    Ok(())
+}
@@ -1 +1,8 @@
+mod compat;
 mod detect;
+#[cfg(feature = "download")]
+mod download;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "arc-swap")]
+mod reloadable;
@@ -0,0 +1,56 @@
+use super::super::device_detection::{EvidenceName, ManagerConfig, PropertyName};
+use super::super::reloadable::ReloadableManager;
+
+const IPHONE_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 15_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.2 Mobile/15E148 Safari/604.1";
+
+fn config() -> ManagerConfig {
+    ManagerConfig::builder(std::path::Path::new("data.hash"))
+        .property_names(&[PropertyName::BrowserName])
+        .build()
+}
+
+#[test]
+fn test_reload_during_detections() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = ReloadableManager::new(config())?;
+
+    std::thread::scope(|scope| -> Result<(), Box<dyn std::error::Error>> {
+        let detections = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    (0..1_000).all(|_| {
+                        manager
+                            .detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])
+                            .and_then(|res| res.get_value_as_string(PropertyName::BrowserName))
+                            .is_ok_and(|name| name.as_deref() == Some("Mobile Safari"))
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..3 {
+            manager.reload(config())?;
+        }
+        for detection in detections {
+            assert!(detection.join().expect("Detection thread should not panic"));
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+fn test_result_outlives_reload() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = ReloadableManager::new(config())?;
+    let res = manager.detect(&[EvidenceName::UserAgent.value(IPHONE_UA)])?;
+
+    // The manager which created the result is no longer referenced by the reloadable one
+    manager.reload(config())?;
+    manager.reload(config())?;
+
+    assert_eq!(
+        res.get_value_as_string(PropertyName::BrowserName)?.as_deref(),
+        Some("Mobile Safari")
+    );
+
+    Ok(())
+}
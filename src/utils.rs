@@ -1,14 +1,16 @@
 use super::bindings;
+use super::device_detection::PropertyName;
 use super::utils::FiftyOneDegreesError::{
     AssertionError, CStringCreationError, InternalApiError,
 };
 use std::ffi::CString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use strum_macros::{AsRefStr, Display};
 use thiserror::Error;
 
+/// Input which could not be passed to the C library as a C string, e.g. because it contains NUL.
 #[derive(Debug, Display)]
-pub(crate) enum CStringKind {
+pub enum CStringKind {
     #[strum(serialize = "evidence key")]
     FilePath,
     #[strum(serialize = "evidence value")]
@@ -21,8 +23,9 @@ pub(crate) enum CStringKind {
     HashResultSeparator,
 }
 
+/// Operation during which an error occurred.
 #[derive(Debug, Display)]
-pub(crate) enum Operation {
+pub enum Operation {
     #[strum(serialize = "read data file")]
     ReadDataFile,
     #[strum(serialize = "initialize manager")]
@@ -39,75 +42,262 @@ pub(crate) enum Operation {
 pub(crate) enum ReadFileError {
     #[strum(serialize = "file does not exist")]
     NotExists,
-    #[strum(serialize = "is not a file")]
+    #[strum(serialize = "is not a regular file, e.g. a directory")]
     IsNotFile,
+    #[strum(serialize = "file is empty")]
+    IsEmpty,
 }
 
+/// Error returned by the manager and the results, see [`FiftyOneDegreesError::kind`].
 #[derive(Debug, Error)]
-pub(crate) enum FiftyOneDegreesError {
+#[non_exhaustive]
+pub enum FiftyOneDegreesError {
     #[error("CString creation error for: {0}")]
     CStringCreationError(CStringKind),
     #[error(
-        "FiftyOneDegrees internal API error for operation: {0}, status code: {1}, message: {2}, error: {3})"
+        "FiftyOneDegrees internal API error for operation: {0}, status code: {1:?}, message: {2}, error: {3})"
     )]
-    InternalApiError(Operation, u32, &'static str, &'static str),
+    InternalApiError(Operation, StatusCode, &'static str, &'static str),
     #[error("FiftyOneDegrees unsafe operation error: {0}")]
     UnsafeOperationError(String),
     #[error("FiftyOneDegrees assertion error for operation {0}: {1}")]
     AssertionError(Operation, &'static str),
     #[error("FiftyOneDegrees IO error: {0}, cause: {1:?}")]
     IOError(&'static str, Option<std::io::Error>),
+    #[error(
+        "FiftyOneDegrees temp file error: failed to copy the data file to {}, check that the directory exists and is writable",
+        .dir.display()
+    )]
+    TempFileError { dir: PathBuf },
+    #[error("FiftyOneDegrees value parse error: value {raw:?} of property {property} is not a valid {target}")]
+    ValueParse {
+        property: String,
+        raw: String,
+        target: &'static str,
+    },
+    #[error("FiftyOneDegrees required properties not present in the data file: {}", .0.join(", "))]
+    RequiredPropertyMissing(Vec<String>),
+    #[cfg(feature = "download")]
+    #[error("FiftyOneDegrees download error: failed to download the data file from {url}: {cause}")]
+    DownloadError { url: String, cause: String },
+    #[error("FiftyOneDegrees property {0} is outside the properties of this detection")]
+    PropertyNotExposed(String),
+    #[error("FiftyOneDegrees data tier mismatch: {required} data file required, got {actual}")]
+    TierMismatch { required: String, actual: String },
+    #[error(
+        "FiftyOneDegrees manager poisoned: a previous detection failed with status {0:?}, create a new manager"
+    )]
+    ManagerPoisoned(StatusCode),
+    #[error("FiftyOneDegrees unknown capability: {name}, the closest property is {nearest}")]
+    UnknownCapability {
+        name: String,
+        nearest: PropertyName,
+    },
+}
+
+/// Coarse cause of a [`FiftyOneDegreesError`], for callers deciding how to handle it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The data file is missing, unreadable or doesn't match the configuration, reloading it
+    /// or fixing the deployment is needed.
+    DataFile,
+    /// The evidence, property or value requested by the caller was rejected, retrying the same
+    /// call fails the same way.
+    InvalidInput,
+    /// The C library failed, with the status code it reported if any.
+    Internal(Option<StatusCode>),
 }
 
 impl FiftyOneDegreesError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AssertionError(Operation::ReadDataFile | Operation::InitManager, _)
+            | FiftyOneDegreesError::IOError(..)
+            | FiftyOneDegreesError::TempFileError { .. }
+            | FiftyOneDegreesError::RequiredPropertyMissing(_)
+            | FiftyOneDegreesError::TierMismatch { .. } => ErrorKind::DataFile,
+            #[cfg(feature = "download")]
+            FiftyOneDegreesError::DownloadError { .. } => ErrorKind::DataFile,
+            CStringCreationError(_)
+            | AssertionError(..)
+            | FiftyOneDegreesError::ValueParse { .. }
+            | FiftyOneDegreesError::PropertyNotExposed(_)
+            | FiftyOneDegreesError::UnknownCapability { .. } => ErrorKind::InvalidInput,
+            InternalApiError(_, status, ..) | FiftyOneDegreesError::ManagerPoisoned(status) => {
+                ErrorKind::Internal(Some(*status))
+            }
+            FiftyOneDegreesError::UnsafeOperationError(_) => ErrorKind::Internal(None),
+        }
+    }
+
     fn new_read_file_assertion_error(error: &'static ReadFileError) -> Self {
         AssertionError(Operation::ReadDataFile, error.as_ref())
     }
 }
 
-pub(crate) type FiftyOneDegreesResult<T> = Result<T, FiftyOneDegreesError>;
+pub type FiftyOneDegreesResult<T> = Result<T, FiftyOneDegreesError>;
 
 pub(crate) fn build_cstring(kind: CStringKind, str: &str) -> FiftyOneDegreesResult<CString> {
     CString::new(str).map_err(|_| CStringCreationError(kind))
 }
 
+pub(crate) fn build_cstring_from_bytes(
+    kind: CStringKind,
+    bytes: &[u8],
+) -> FiftyOneDegreesResult<CString> {
+    CString::new(bytes).map_err(|_| CStringCreationError(kind))
+}
+
+/// Status code returned by the C library, see [`StatusCode::from_raw`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusCode {
+    Success,
+    InsufficientMemory,
+    CorruptData,
+    IncorrectVersion,
+    FileNotFound,
+    FileBusy,
+    FileFailure,
+    NotSet,
+    PointerOutOfBounds,
+    NullPointer,
+    TooManyOpenFiles,
+    ReqPropNotPresent,
+    ProfileEmpty,
+    CollectionFailure,
+    FileCopyError,
+    FileExistsError,
+    FileWriteError,
+    FileReadError,
+    FilePermissionDenied,
+    FilePathTooLong,
+    FileEndOfDocument,
+    FileEndOfDocuments,
+    FileEndOfFile,
+    EncodingError,
+    InvalidCollectionConfig,
+    InvalidConfig,
+    InsufficientHandles,
+    CollectionIndexOutOfRange,
+    CollectionOffsetOutOfRange,
+    CollectionFileSeekFail,
+    CollectionFileReadFail,
+    IncorrectIpAddressFormat,
+    TempFileError,
+    /// Status code added to the C library after this list was written
+    Unknown(u32),
+}
+
+impl StatusCode {
+    pub fn from_raw(status: u32) -> Self {
+        match status {
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_SUCCESS => StatusCode::Success,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INSUFFICIENT_MEMORY => StatusCode::InsufficientMemory,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_CORRUPT_DATA => StatusCode::CorruptData,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INCORRECT_VERSION => StatusCode::IncorrectVersion,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_NOT_FOUND => StatusCode::FileNotFound,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_BUSY => StatusCode::FileBusy,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_FAILURE => StatusCode::FileFailure,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_NOT_SET => StatusCode::NotSet,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_POINTER_OUT_OF_BOUNDS => StatusCode::PointerOutOfBounds,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_NULL_POINTER => StatusCode::NullPointer,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_TOO_MANY_OPEN_FILES => StatusCode::TooManyOpenFiles,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_REQ_PROP_NOT_PRESENT => StatusCode::ReqPropNotPresent,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_PROFILE_EMPTY => StatusCode::ProfileEmpty,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_FAILURE => StatusCode::CollectionFailure,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_COPY_ERROR => StatusCode::FileCopyError,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_EXISTS_ERROR => StatusCode::FileExistsError,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_WRITE_ERROR => StatusCode::FileWriteError,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_READ_ERROR => StatusCode::FileReadError,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_PERMISSION_DENIED => StatusCode::FilePermissionDenied,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_PATH_TOO_LONG => StatusCode::FilePathTooLong,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_END_OF_DOCUMENT => StatusCode::FileEndOfDocument,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_END_OF_DOCUMENTS => StatusCode::FileEndOfDocuments,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_END_OF_FILE => StatusCode::FileEndOfFile,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_ENCODING_ERROR => StatusCode::EncodingError,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INVALID_COLLECTION_CONFIG => StatusCode::InvalidCollectionConfig,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INVALID_CONFIG => StatusCode::InvalidConfig,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INSUFFICIENT_HANDLES => StatusCode::InsufficientHandles,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_INDEX_OUT_OF_RANGE => StatusCode::CollectionIndexOutOfRange,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_OFFSET_OUT_OF_RANGE => StatusCode::CollectionOffsetOutOfRange,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_FILE_SEEK_FAIL => StatusCode::CollectionFileSeekFail,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_FILE_READ_FAIL => StatusCode::CollectionFileReadFail,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INCORRECT_IP_ADDRESS_FORMAT => StatusCode::IncorrectIpAddressFormat,
+            bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_TEMP_FILE_ERROR => StatusCode::TempFileError,
+            other => StatusCode::Unknown(other),
+        }
+    }
+
+    /// Returns a short description of the status.
+    pub fn message(self) -> &'static str {
+        match self {
+            StatusCode::Success => "Success",
+            StatusCode::InsufficientMemory => "Lack of memory",
+            StatusCode::CorruptData => "Corrupt data",
+            StatusCode::IncorrectVersion => "Incorrect version",
+            StatusCode::FileNotFound => "File not found",
+            StatusCode::FileBusy => "File busy",
+            StatusCode::FileFailure => "File failure",
+            StatusCode::NotSet => "Not set (should never be returned)",
+            StatusCode::PointerOutOfBounds => "Pointer out of bounds",
+            StatusCode::NullPointer => "Null pointer",
+            StatusCode::TooManyOpenFiles => "Too many open files",
+            StatusCode::ReqPropNotPresent => "Required property not present",
+            StatusCode::ProfileEmpty => "Profile is empty",
+            StatusCode::CollectionFailure => "Collection failure",
+            StatusCode::FileCopyError => "File copy error",
+            StatusCode::FileExistsError => "File exists error",
+            StatusCode::FileWriteError => "File write error",
+            StatusCode::FileReadError => "File read error",
+            StatusCode::FilePermissionDenied => "File permission denied",
+            StatusCode::FilePathTooLong => "File path too long",
+            StatusCode::FileEndOfDocument => "File end of document",
+            StatusCode::FileEndOfDocuments => "File end of documents",
+            StatusCode::FileEndOfFile => "File end of file",
+            StatusCode::EncodingError => "Encoding error",
+            StatusCode::InvalidCollectionConfig => "Invalid collection config",
+            StatusCode::InvalidConfig => "Invalid config",
+            StatusCode::InsufficientHandles => "Insufficient handles",
+            StatusCode::CollectionIndexOutOfRange => "Collection index out of range",
+            StatusCode::CollectionOffsetOutOfRange => "Collection offset out of range",
+            StatusCode::CollectionFileSeekFail => "Collection file seek fail",
+            StatusCode::CollectionFileReadFail => "Collection file read fail",
+            StatusCode::IncorrectIpAddressFormat => "Incorrect IP address format",
+            StatusCode::TempFileError => "Temp file error",
+            StatusCode::Unknown(_) => "Unknown error",
+        }
+    }
+
+    /// Checks whether the status reports a broken data set or memory state, after which the
+    /// C library can't be relied on, as opposed to a problem with the input of one call.
+    pub fn is_fatal(self) -> bool {
+        matches!(
+            self,
+            StatusCode::InsufficientMemory
+                | StatusCode::CorruptData
+                | StatusCode::PointerOutOfBounds
+                | StatusCode::NullPointer
+                | StatusCode::CollectionFailure
+                | StatusCode::CollectionIndexOutOfRange
+                | StatusCode::CollectionOffsetOutOfRange
+                | StatusCode::CollectionFileSeekFail
+                | StatusCode::CollectionFileReadFail
+        )
+    }
+}
+
 pub(crate) fn status_to_error_message(status: u32) -> &'static str {
-    match status {
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_SUCCESS  => "Success",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INSUFFICIENT_MEMORY => "Lack of memory",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_CORRUPT_DATA => "Corrupt data",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INCORRECT_VERSION => "Incorrect version",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_NOT_FOUND => "File not found",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_BUSY => "File busy",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_FAILURE => "File failure",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_NOT_SET => "Not set (should never be returned)",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_POINTER_OUT_OF_BOUNDS => "Pointer out of bounds",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_NULL_POINTER => "Null pointer",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_TOO_MANY_OPEN_FILES => "Too many open files",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_REQ_PROP_NOT_PRESENT => "Required property not present",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_PROFILE_EMPTY => "Profile is empty",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_FAILURE => "Collection failure",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_COPY_ERROR => "File copy error",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_EXISTS_ERROR => "File exists error",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_WRITE_ERROR => "File write error",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_READ_ERROR => "File read error",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_PERMISSION_DENIED => "File permission denied",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_PATH_TOO_LONG => "File path too long",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_END_OF_DOCUMENT => "File end of document",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_END_OF_DOCUMENTS => "File end of documents",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_FILE_END_OF_FILE => "File end of file",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_ENCODING_ERROR => "Encoding error",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INVALID_COLLECTION_CONFIG => "Invalid collection config",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INVALID_CONFIG => "Invalid config",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INSUFFICIENT_HANDLES => "Insufficient handles",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_INDEX_OUT_OF_RANGE => "Collection index out of range",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_OFFSET_OUT_OF_RANGE => "Collection offset out of range",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_FILE_SEEK_FAIL => "Collection file seek fail",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_COLLECTION_FILE_READ_FAIL => "Collection file read fail",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_INCORRECT_IP_ADDRESS_FORMAT => "Incorrect IP address format",
-        bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_TEMP_FILE_ERROR => "Temp file error",
-        _ => "Unknown error"
+    StatusCode::from_raw(status).message()
+}
+
+/// Reads a string from the data set strings collection, `None` for a null pointer.
+pub(crate) fn read_fiftyone_string(string: *const bindings::fiftyoneDegreesString) -> Option<String> {
+    if string.is_null() {
+        return None;
     }
+    let value = unsafe { std::ffi::CStr::from_ptr(std::ptr::addr_of!((*string).value)) };
+    Some(value.to_string_lossy().to_string())
 }
 
 pub(crate) fn ger_error_msg(exception: *mut bindings::fiftyoneDegreesException) -> &'static str {
@@ -127,16 +317,27 @@ pub(crate) fn ger_error_msg(exception: *mut bindings::fiftyoneDegreesException)
     }
 }
 
+/// Creates an exception to pass to a call of the C library, which sets its status on failure.
+pub(crate) fn new_exception() -> bindings::fiftyoneDegreesException {
+    bindings::fiftyoneDegreesException {
+        status: bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_NOT_SET,
+        ..Default::default()
+    }
+}
+
+/// Fails with the status of the exception if the C library set one, see [`new_exception`].
 pub(crate) fn verify_exception(
     exception: *mut bindings::fiftyoneDegreesException,
     operation: Operation,
 ) -> FiftyOneDegreesResult<()> {
     if !exception.is_null() {
         let status = unsafe { *exception }.status;
-        if status != bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_SUCCESS {
+        if status != bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_NOT_SET
+            && status != bindings::e_fiftyone_degrees_status_code_FIFTYONE_DEGREES_STATUS_SUCCESS
+        {
             return Err(InternalApiError(
                 operation,
-                status,
+                StatusCode::from_raw(status),
                 status_to_error_message(status),
                 ger_error_msg(exception),
             ));
@@ -156,5 +357,11 @@ pub(crate) fn verify_data_file_path(path: &Path) -> FiftyOneDegreesResult<()> {
             &ReadFileError::IsNotFile,
         ));
     }
+    // An empty file would otherwise fail in the C library with a generic corrupt data status
+    if path.metadata().is_ok_and(|metadata| metadata.len() == 0) {
+        return Err(FiftyOneDegreesError::new_read_file_assertion_error(
+            &ReadFileError::IsEmpty,
+        ));
+    }
     Ok(())
 }